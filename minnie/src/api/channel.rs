@@ -1,6 +1,7 @@
 use crate::http::*;
 use enumset::*;
-use futures::future::try_join_all;
+use futures::future::{self, FutureExt};
+use futures::stream::{self, StreamExt, TryStreamExt};
 use minnie_errors::*;
 use minnie_model::channel::*;
 use minnie_model::message::*;
@@ -8,6 +9,17 @@ use minnie_model::types::*;
 use minnie_model::user::*;
 use std::borrow::Cow;
 use std::fmt::{Display, Write};
+use std::time::{Duration, SystemTime};
+use tokio::time;
+
+/// The delay between reactions added by [`MessageOps::react_all`].
+const REACTION_PACING: Duration = Duration::from_millis(250);
+
+/// The maximum number of API calls [`ChannelOps::delete_messages`] will make at once.
+pub const DELETE_MESSAGES_CONCURRENCY: usize = 4;
+
+/// The maximum age of messages that can be bulk deleted, with a minute of leeway for clock skew.
+const BULK_DELETE_MAX_AGE: Duration = Duration::from_secs(60 * 60 * 24 * 14 - 60);
 
 /// Performs operations relating to a Discord channel.
 ///
@@ -88,22 +100,28 @@ impl <'a> ChannelOps<'a> {
 
     /// Deletes a list of messages.
     ///
-    /// This will make an API call for each 100 messages in the list. The API calls will be
-    /// dispatched simultaneously.
+    /// Discord does not allow bulk deleting messages older than 14 days. Any such messages in
+    /// the list are deleted with an individual API call each, while the rest are bulk deleted
+    /// with an API call for each 100 messages. At most [`DELETE_MESSAGES_CONCURRENCY`] of these
+    /// API calls will be in flight at once.
     pub async fn delete_messages(self, messages: impl Into<Cow<'a, [MessageId]>>) -> Result<()> {
         let messages = messages.into();
-        if messages.len() == 1 {
-            self.raw.delete_message(self.id, messages[0]).await?;
-        } else if messages.len() <= 100 {
-            self.raw.bulk_delete_message(self.id, &messages).await?;
+        let cutoff = SystemTime::now() - BULK_DELETE_MAX_AGE;
+        let (bulk, single): (Vec<MessageId>, Vec<MessageId>) =
+            messages.iter().partition(|x| x.0.timestamp() > cutoff);
+
+        let id = self.id;
+        let raw = &self.raw;
+        let bulk_futs = bulk.chunks(100).map(|chunk| if chunk.len() == 1 {
+            raw.clone().delete_message(id, chunk[0]).boxed()
         } else {
-            let mut delete_futs = Vec::new();
-            for chunk in messages.chunks(100) {
-                delete_futs.push(self.raw.clone().bulk_delete_message(self.id, chunk));
-            }
-            try_join_all(delete_futs).await?;
-        }
-        Ok(())
+            raw.clone().bulk_delete_message(id, chunk).boxed()
+        });
+        let single_futs = single.iter().map(|msg| raw.clone().delete_message(id, *msg).boxed());
+        stream::iter(bulk_futs.chain(single_futs))
+            .buffer_unordered(DELETE_MESSAGES_CONCURRENCY)
+            .try_for_each(|_| future::ready(Ok(())))
+            .await
     }
 
     /// Completely overwrites the permission overwrite for a given user or role.
@@ -177,19 +195,79 @@ impl <'a> MessageOps<'a> {
         self.raw.get_channel_message(self.channel_id, self.message_id).await
     }
 
+    /// Retrieves the message this message replies to.
+    ///
+    /// This uses the referenced message included with the message if Discord sent one, and
+    /// otherwise retrieves it using the message's reference. Returns `None` if the message does
+    /// not reference another message, or if the referenced message was deleted.
+    pub async fn referenced(self) -> Result<Option<Message>> {
+        let message = self.raw.clone().get_channel_message(self.channel_id, self.message_id).await?;
+        if let Some(referenced) = message.referenced_message {
+            return Ok(Some(*referenced))
+        }
+        let (channel_id, message_id) = match message.message_reference {
+            Some(MessageReference { message_id: Some(id), channel_id, .. }) =>
+                (channel_id.unwrap_or(self.channel_id), id),
+            _ => return Ok(None),
+        };
+        match self.raw.get_channel_message(channel_id, message_id).await {
+            Ok(message) => Ok(Some(message)),
+            Err(e) if e.is_not_found() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Sets whether embeds are suppressed on this message.
+    ///
+    /// Discord replaces all of a message's flags when they are edited, so this retrieves the
+    /// message first, and then edits it with only the `SuppressEmbeds` flag changed. A flag
+    /// changed by someone else between these two API calls may be overwritten.
+    pub async fn suppress_embeds(self, suppress: bool) -> Result<Message> {
+        let message = self.raw.clone().get_channel_message(self.channel_id, self.message_id).await?;
+        let mut flags = message.flags;
+        if suppress {
+            flags.insert(MessageFlag::SuppressEmbeds);
+        } else {
+            flags.remove(MessageFlag::SuppressEmbeds);
+        }
+        let params = EditMessageParams::new().flags(flags);
+        self.raw.edit_message(self.channel_id, self.message_id, params).await
+    }
+
     /// Reacts to this message.
-    pub async fn react(self, emoji: &EmojiRef) -> Result<()> {
-        self.raw.create_reaction(self.channel_id, self.message_id, emoji).await
+    pub async fn react(self, emoji: impl Into<EmojiRef>) -> Result<()> {
+        self.raw.create_reaction(self.channel_id, self.message_id, &emoji.into()).await
+    }
+
+    /// Reacts to this message with each emoji in a list, in order.
+    ///
+    /// Discord limits adding reactions to roughly one every 250 milliseconds, so the reactions
+    /// are added one at a time, and this method waits between each API call. If any reaction
+    /// fails, the remaining reactions are not added and the error is returned.
+    pub async fn react_all(self, emojis: impl IntoIterator<Item = EmojiRef>) -> Result<()> {
+        let mut is_first = true;
+        for emoji in emojis {
+            if !is_first {
+                time::delay_for(REACTION_PACING).await;
+            }
+            is_first = false;
+            self.clone().react(emoji).await?;
+        }
+        Ok(())
     }
 
     /// Removes the bot's reaction to this message.
-    pub async fn delete_own_reaction(self, emoji: &EmojiRef) -> Result<()> {
-        self.raw.delete_own_reaction(self.channel_id, self.message_id, emoji).await
+    pub async fn delete_own_reaction(self, emoji: impl Into<EmojiRef>) -> Result<()> {
+        self.raw.delete_own_reaction(self.channel_id, self.message_id, &emoji.into()).await
     }
 
     /// Removes another user's reaction to this message.
-    pub async fn delete_reaction(self, emoji: &EmojiRef, user: UserId) -> Result<()> {
-        self.raw.delete_user_reaction(self.channel_id, self.message_id, emoji, user).await
+    pub async fn delete_user_reaction(
+        self, emoji: impl Into<EmojiRef>, user: impl Into<UserId>,
+    ) -> Result<()> {
+        self.raw.delete_user_reaction(
+            self.channel_id, self.message_id, &emoji.into(), user.into(),
+        ).await
     }
 
     /// Retrieves a list of users who reacted with a particular emoji to this message.
@@ -201,21 +279,32 @@ impl <'a> MessageOps<'a> {
     }
 
     /// Deletes all reactions from a message.
-    pub async fn clear_reactions(self) -> Result<()> {
+    pub async fn delete_all_reactions(self) -> Result<()> {
         self.raw.delete_all_reactions(self.channel_id, self.message_id).await
     }
 
     /// Deletes all reactions with a given emoji from a message.
-    pub async fn clear_reactions_for_emoji(self, emoji: &EmojiRef) -> Result<()> {
+    pub async fn delete_reactions_for_emoji(self, emoji: impl Into<EmojiRef>) -> Result<()> {
         self.raw.delete_all_reactions_for_emoji(
-            self.channel_id, self.message_id, emoji,
+            self.channel_id, self.message_id, &emoji.into(),
         ).await
     }
 
     /// Edits this message.
-    /// 
-    /// This has similar parameters to posting messages, but only [`content`](`EditFut::content`)
-    /// and [`embed`](`EditFut::embed`) are supported.
+    ///
+    /// This has similar parameters to posting messages. For information on what properties can
+    /// be changed, see the methods of [`EditFut`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::message::Message;
+    /// async fn strike_out(ctx: DiscordContext, msg: &Message) -> Result<Message> {
+    ///     ctx.message(msg.channel_id, msg.id).edit().content(format!("~~{}~~", msg.content)).await
+    /// }
+    /// ```
     pub fn edit(self) -> EditFut<'a> {
         EditFut::new(self)
     }
@@ -226,11 +315,16 @@ impl <'a> MessageOps<'a> {
     }
 
     /// Pins this message to its channel.
+    ///
+    /// This requires the `Manage Messages` permission. A channel can have at most 50 pinned
+    /// messages, and the list of pins can be retrieved with [`ChannelOps::get_pinned_messages`].
     pub async fn pin(self) -> Result<()> {
         self.raw.add_pinned_channel_message(self.channel_id, self.message_id).await
     }
 
     /// Unpins this message from its channel.
+    ///
+    /// This requires the `Manage Messages` permission.
     pub async fn unpin(self) -> Result<()> {
         self.raw.delete_pinned_channel_message(self.channel_id, self.message_id).await
     }
@@ -248,6 +342,7 @@ fut_builder! {
         params: ModifyChannelParams<'a>,
     }
     into_async!(|ops, data| -> Result<Channel> {
+        data.params.validate()?;
         ops.raw.modify_channel(ops.id, data.params).await
     });
 
@@ -362,6 +457,45 @@ fut_builder! {
     }
 }
 
+fn check_allowed_mentions(mentions: &Option<AllowedMentions>) -> Result<()> {
+    if let Some(AllowedMentions { parse: Some(parse), users, roles, .. }) = mentions {
+        ensure!(!parse.contains(MentionType::Users) || users.is_none(),
+                InvalidInput, "Cannot allow mentioning all users and a list of users at once.");
+        ensure!(!parse.contains(MentionType::Roles) || roles.is_none(),
+                InvalidInput, "Cannot allow mentioning all roles and a list of roles at once.");
+    }
+    Ok(())
+}
+
+fn check_embeds<'a>(
+    embed: &mut Option<Embed<'a>>, embeds: &mut Option<Vec<Embed<'a>>>,
+) -> Result<()> {
+    if let Some(embeds) = embeds {
+        if let Some(embed) = embed.take() {
+            embeds.insert(0, embed);
+        }
+        ensure!(embeds.len() <= MESSAGE_EMBED_COUNT_LIMIT,
+                InvalidInput, "Messages cannot have more than 10 embeds.");
+    }
+    let total: usize = embed.iter().chain(embeds.iter().flatten()).map(Embed::total_length).sum();
+    ensure!(total <= EMBED_TOTAL_LIMIT,
+            InvalidInput, "Embeds cannot contain more than 6000 characters in total.");
+    Ok(())
+}
+
+/// Checks the embeds of an edit, where `Some(None)` clears a field rather than leaving it unset.
+fn check_edit_embeds<'a>(
+    embed: &mut Option<Option<Embed<'a>>>, embeds: &mut Option<Option<Vec<Embed<'a>>>>,
+) -> Result<()> {
+    match embeds {
+        Some(embeds) if embeds.is_some() => {
+            let mut new_embed = embed.take().flatten();
+            check_embeds(&mut new_embed, embeds)
+        }
+        _ => check_embeds(embed.as_mut().unwrap_or(&mut None), &mut None),
+    }
+}
+
 fut_builder! {
     ('a, post_fut_mod, ChannelOps, self)
 
@@ -372,16 +506,25 @@ fut_builder! {
         params: CreateMessageParams<'a>,
         files: Vec<CreateMessageFile<'a>>,
     }
-    into_async!(|ops, data| -> Result<Message> {
-        if let Some(mentions) = &data.params.allowed_mentions {
-            if mentions.parse.is_some() && (mentions.users.is_some() || mentions.roles.is_some()) {
-                bail!(InvalidInput, "Cannot use allowed mention types in addition to allowed \
-                                     user or roles.");
-            }
+    into_async!(|ops, mut data| -> Result<Message> {
+        check_allowed_mentions(&data.params.allowed_mentions)?;
+        check_embeds(&mut data.params.embed, &mut data.params.embeds)?;
+        if let Some(components) = &data.params.components {
+            check_components(components)?;
         }
-        if data.files.is_empty() && data.params.content.is_none() && data.params.embed.is_none() {
-            bail!(InvalidInput, "At least one of `content` or `embed` must be set, or a file must \
-                                 be uploaded.");
+        if let Some(reference) = &data.params.message_reference {
+            ensure!(reference.message_id.is_some(),
+                    InvalidInput, "`reply_to` must be set to use `fail_if_not_exists`.");
+        }
+        if let Some(stickers) = &data.params.sticker_ids {
+            ensure!(stickers.len() <= MESSAGE_STICKER_COUNT_LIMIT,
+                    InvalidInput, "Messages cannot have more than 3 stickers.");
+        }
+        let has_embeds = data.params.embed.is_some() || data.params.embeds.is_some();
+        let has_stickers = data.params.sticker_ids.is_some();
+        if data.files.is_empty() && data.params.content.is_none() && !has_embeds && !has_stickers {
+            bail!(InvalidInput, "At least one of `content`, `embeds` or `sticker_ids` must be \
+                                 set, or a file must be uploaded.");
         }
         ops.raw.create_message(ops.id, data.params, data.files).await
     });
@@ -419,11 +562,62 @@ fut_builder! {
         self.params.embed = Some(embed.into());
     }
 
+    /// Adds an embed to the post.
+    ///
+    /// Up to 10 embeds may be attached to a single post.
+    pub fn add_embed(&mut self, embed: impl Into<Embed<'a>>) {
+        self.params.embeds.get_or_insert_with(Vec::new).push(embed.into());
+    }
+
+    /// Sets the components of the post, such as buttons or select menus.
+    ///
+    /// A [`ComponentBuilder`] can be used to create a list of components.
+    pub fn components(&mut self, components: Vec<Component>) {
+        self.params.components = Some(components);
+    }
+
+    /// Adds a sticker to the post.
+    ///
+    /// Up to 3 stickers may be attached to a single post.
+    pub fn sticker(&mut self, sticker: impl Into<StickerId>) {
+        self.params.sticker_ids.get_or_insert_with(Vec::new).push(sticker.into());
+    }
+
     /// Attaches a file to the message.
     pub fn file(&mut self, file: CreateMessageFile<'a>) {
         self.files.push(file);
     }
 
+    /// Makes this post a reply to another message in the same channel.
+    pub fn reply_to(&mut self, message: impl Into<MessageId>) {
+        self.params.message_reference
+            .get_or_insert_with(Default::default)
+            .message_id = Some(message.into());
+    }
+
+    /// Sets whether the post should fail if the message it replies to no longer exists.
+    ///
+    /// If this is not set, Discord defaults to failing.
+    pub fn fail_if_not_exists(&mut self, fail: bool) {
+        self.params.message_reference
+            .get_or_insert_with(Default::default)
+            .fail_if_not_exists = Some(fail);
+    }
+
+    /// Sets whether the author of the message being replied to should be mentioned.
+    ///
+    /// If no other allowed mentions have been set, this will disallow all other mentions.
+    pub fn mention_replied_user(&mut self, mention: bool) {
+        self.params.allowed_mentions
+            .get_or_insert_with(Default::default)
+            .replied_user = mention;
+    }
+
+    /// Sets the mentions allowed in the message.
+    pub fn allowed_mentions(&mut self, mentions: AllowedMentions) {
+        self.params.allowed_mentions = Some(mentions);
+    }
+
     /// Disallows all mentions.
     pub fn disallow_mentions(&mut self) {
         self.params.allowed_mentions = Some(AllowedMentions::default());
@@ -568,18 +762,58 @@ fut_builder! {
     struct EditFut {
         params: EditMessageParams<'a>,
     }
-    into_async!(|ops, data| -> Result<Message> {
+    into_async!(|ops, mut data| -> Result<Message> {
+        check_allowed_mentions(&data.params.allowed_mentions)?;
+        check_edit_embeds(&mut data.params.embed, &mut data.params.embeds)?;
+        if let Some(Some(components)) = &data.params.components {
+            check_components(components)?;
+        }
         ops.raw.edit_message(ops.channel_id, ops.message_id, data.params).await
     });
 
     /// Sets the content of the post.
+    ///
+    /// Setting the content to an empty string removes it from the message.
     pub fn content(&mut self, content: impl Into<Cow<'a, str>>) {
-        self.params.content = Some(content.into());
+        self.params.content = Some(Some(content.into()));
+    }
+
+    /// Removes the content of the post.
+    pub fn clear_content(&mut self) {
+        self.params.content = Some(None);
     }
 
     /// Sets the embed of the post.
+    ///
+    /// This overrides an earlier call to [`clear_embeds`](`EditFut::clear_embeds`).
     pub fn embed(&mut self, embed: impl Into<Embed<'a>>) {
-        self.params.embed = Some(embed.into());
+        if let Some(None) = self.params.embeds {
+            self.params.embeds = None;
+        }
+        self.params.embed = Some(Some(embed.into()));
+    }
+
+    /// Sets the embeds of the post, replacing any existing embeds.
+    ///
+    /// This overrides an earlier call to [`clear_embeds`](`EditFut::clear_embeds`).
+    pub fn embeds(&mut self, embeds: impl IntoIterator<Item = Embed<'a>>) {
+        self.params.embeds = Some(Some(embeds.into_iter().collect()));
+    }
+
+    /// Removes all embeds from the post.
+    pub fn clear_embeds(&mut self) {
+        self.params.embed = None;
+        self.params.embeds = Some(None);
+    }
+
+    /// Sets the components of the post, replacing any existing components.
+    pub fn components(&mut self, components: Vec<Component>) {
+        self.params.components = Some(Some(components));
+    }
+
+    /// Removes all components from the post.
+    pub fn clear_components(&mut self) {
+        self.params.components = Some(None);
     }
 
     /// Sets the new flags on this post.
@@ -589,4 +823,14 @@ fut_builder! {
     pub fn flags(&mut self, flags: impl Into<EnumSet<MessageFlag>>) {
         self.params.flags = Some(flags.into());
     }
+
+    /// Sets the mentions allowed in the message.
+    pub fn allowed_mentions(&mut self, mentions: AllowedMentions) {
+        self.params.allowed_mentions = Some(mentions);
+    }
+
+    /// Disallows all mentions.
+    pub fn disallow_mentions(&mut self) {
+        self.params.allowed_mentions = Some(AllowedMentions::default());
+    }
 }
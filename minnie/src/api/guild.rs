@@ -1,10 +1,23 @@
+use chrono::{DateTime, Utc};
 use crate::http::*;
-use futures::future::try_join_all;
+use enumset::*;
+use futures::future::{try_join_all, BoxFuture, FutureExt};
+use futures::stream::Stream;
+use fxhash::FxHashMap;
 use minnie_errors::*;
+use minnie_model::audit_log::*;
 use minnie_model::channel::*;
 use minnie_model::guild::*;
 use minnie_model::types::*;
+use minnie_model::user::*;
 use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// The number of entries requested at once by [`AuditLogStream`].
+const AUDIT_LOG_PAGE_SIZE: u32 = 100;
 
 /// Performs operations relating to guilds.
 ///
@@ -23,6 +36,16 @@ impl <'a> GuildOps<'a> {
 
     // TODO: Create Guilds
 
+    /// Retrieves this guild's settings.
+    pub async fn get(self) -> Result<Guild> {
+        self.raw.get_guild(self.id, GetGuildParams::new()).await
+    }
+
+    /// Retrieves this guild's settings, including its approximate member and presence counts.
+    pub async fn get_with_counts(self) -> Result<Guild> {
+        self.raw.get_guild(self.id, GetGuildParams::new().with_counts(true)).await
+    }
+
     /// Modifies the guild's settings.
     ///
     /// For information on what properties can be set, see the methods of [`ModifyGuildFut`].
@@ -40,12 +63,78 @@ impl <'a> GuildOps<'a> {
         self.raw.get_guild_channels(self.id).await
     }
 
+    /// Gets a list of channels in this guild, grouped by the category they belong to.
+    ///
+    /// Categories and the channels in them are sorted by their position, matching the order the
+    /// Discord client displays them in. The first entry always has a category of `None`, and
+    /// contains the channels that do not belong to any category.
+    pub async fn channels_by_category(self) -> Result<Vec<(Option<Channel>, Vec<Channel>)>> {
+        let mut channels = self.get_channels().await?;
+        channels.sort_by_key(|x| (x.position, x.id));
+        let (categories, channels): (Vec<_>, Vec<_>) =
+            channels.into_iter().partition(Channel::is_category);
+
+        let mut groups = Vec::with_capacity(categories.len() + 1);
+        let mut group_idx = FxHashMap::default();
+        groups.push((None, Vec::new()));
+        for category in categories {
+            group_idx.insert(category.id.0, groups.len());
+            groups.push((Some(category), Vec::new()));
+        }
+        for channel in channels {
+            let idx = channel.parent_id.and_then(|x| group_idx.get(&x.0).copied()).unwrap_or(0);
+            groups[idx].1.push(channel);
+        }
+        Ok(groups)
+    }
+
     // TODO: Create Channel
-    // TODO: Modify Guild Channel Position
+
+    /// Changes the positions of channels in this guild.
+    ///
+    /// For information on how to specify the channels to move, see the methods of
+    /// [`ReorderChannelsFut`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::types::{ChannelId, GuildId};
+    /// async fn swap_channels(
+    ///     ctx: DiscordContext, guild: GuildId, a: ChannelId, b: ChannelId,
+    /// ) -> Result<()> {
+    ///     ctx.guild(guild).reorder_channels().move_channel(a, 1).move_channel(b, 0).await
+    /// }
+    /// ```
+    pub fn reorder_channels(self) -> ReorderChannelsFut<'a> {
+        ReorderChannelsFut::new(self)
+    }
+
     // TODO: List Guild Members
     // TODO: Add Guild Member
-    // TODO: Get Guild Bans
-    // TODO: Get Guild Ban
+
+    /// Retrieves a list of users banned from this guild.
+    pub async fn bans(self) -> Result<Vec<GuildBan>> {
+        self.raw.get_guild_bans(self.id).await
+    }
+
+    /// Retrieves information about a ban on a user in this guild.
+    pub async fn get_ban(self, user: impl Into<UserId>) -> Result<GuildBan> {
+        self.raw.get_guild_ban(self.id, user.into()).await
+    }
+
+    /// Bans a user from this guild.
+    ///
+    /// For information on what properties can be set, see the methods of [`BanFut`].
+    pub fn ban(self, user: impl Into<UserId>) -> BanFut<'a> {
+        self.member(user).ban()
+    }
+
+    /// Unbans a user from this guild.
+    pub async fn unban(self, user: impl Into<UserId>) -> Result<()> {
+        self.raw.remove_guild_ban(self.id, user.into()).await
+    }
 
     /// Changes the bot's username on the guild.
     pub async fn change_nick(self, nick: impl AsRef<str>) -> Result<()> {
@@ -57,8 +146,21 @@ impl <'a> GuildOps<'a> {
         self.raw.get_guild_roles(self.id).await
     }
 
-    // TODO: Create Guild Role
-    // TODO: Modify Guild Role Positions
+    /// Creates a new role in this guild.
+    ///
+    /// For information on what properties can be set, see the methods of [`CreateRoleFut`].
+    /// If no properties are set, Discord creates a role named `new role` with no permissions.
+    pub fn create_role(self) -> CreateRoleFut<'a> {
+        CreateRoleFut::new(self)
+    }
+
+    /// Changes the positions of roles in this guild's hierarchy.
+    pub async fn set_role_positions(
+        self, positions: Vec<ModifyGuildRolePositionParams>,
+    ) -> Result<()> {
+        self.raw.modify_guild_role_position(self.id, positions).await
+    }
+
     // TODO: Modify Guild Role
     // TODO: Delete Guild Role
     // TODO: Begin Guild Prune
@@ -86,9 +188,145 @@ impl <'a> GuildOps<'a> {
         Ok(result.code.map(|x| format!("https://discord.gg/{}", x)))
     }
 
+    /// Returns a stream of the entries in this guild's audit log, from newest to oldest.
+    ///
+    /// For information on how to filter the entries, see the methods of [`AuditLogStream`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::audit_log::AuditLogEvent;
+    /// # use minnie::model::types::GuildId;
+    /// use futures::TryStreamExt;
+    ///
+    /// async fn count_kicks(ctx: DiscordContext, guild: GuildId) -> Result<usize> {
+    ///     let stream = ctx.guild(guild).audit_log_iter().action_type(AuditLogEvent::MemberKick);
+    ///     let kicks: Vec<_> = stream.try_collect().await?;
+    ///     Ok(kicks.len())
+    /// }
+    /// ```
+    pub fn audit_log_iter(self) -> AuditLogStream<'a> {
+        AuditLogStream::new(self)
+    }
+
     routes_wrapper!(self, &mut self.raw);
 }
 
+/// A stream of the entries in a guild's audit log, from newest to oldest.
+///
+/// Entries are requested a page at a time as the stream is polled. The users, webhooks and
+/// integrations referenced by an entry can be looked up with [`AuditLogStream::get_user`],
+/// [`AuditLogStream::get_webhook`] and [`AuditLogStream::get_integration`] once it has been
+/// returned.
+///
+/// Instances can be obtained via [`GuildOps::audit_log_iter`].
+#[must_use = "streams do nothing unless polled"]
+pub struct AuditLogStream<'a> {
+    ops: GuildOps<'a>,
+    params: GetGuildAuditLogParams<'a>,
+    entries: VecDeque<AuditLogEntry>,
+    users: FxHashMap<UserId, User>,
+    webhooks: FxHashMap<WebhookId, Webhook>,
+    integrations: FxHashMap<IntegrationId, Integration>,
+    fut: Option<BoxFuture<'a, Result<AuditLog>>>,
+    finished: bool,
+}
+impl <'a> AuditLogStream<'a> {
+    fn new(ops: GuildOps<'a>) -> Self {
+        AuditLogStream {
+            ops,
+            params: GetGuildAuditLogParams::new().limit(AUDIT_LOG_PAGE_SIZE),
+            entries: VecDeque::new(),
+            users: FxHashMap::default(),
+            webhooks: FxHashMap::default(),
+            integrations: FxHashMap::default(),
+            fut: None,
+            finished: false,
+        }
+    }
+
+    /// Only returns entries for actions performed by a given user.
+    pub fn user_id(mut self, user: impl Into<UserId>) -> Self {
+        self.params.user_id = Some(user.into());
+        self
+    }
+
+    /// Only returns entries for a given type of action.
+    pub fn action_type(mut self, action_type: AuditLogEvent) -> Self {
+        self.params.action_type = Some(action_type);
+        self
+    }
+
+    /// Only returns entries older than a given entry.
+    pub fn before(mut self, entry: impl Into<AuditLogEntryId>) -> Self {
+        self.params.before = Some(entry.into());
+        self
+    }
+
+    /// Returns a user referenced by an entry returned by this stream.
+    pub fn get_user(&self, id: impl Into<UserId>) -> Option<&User> {
+        self.users.get(&id.into())
+    }
+
+    /// Returns a webhook referenced by an entry returned by this stream.
+    pub fn get_webhook(&self, id: impl Into<WebhookId>) -> Option<&Webhook> {
+        self.webhooks.get(&id.into())
+    }
+
+    /// Returns an integration referenced by an entry returned by this stream.
+    pub fn get_integration(&self, id: impl Into<IntegrationId>) -> Option<&Integration> {
+        self.integrations.get(&id.into())
+    }
+
+    fn push_page(&mut self, page: AuditLog) {
+        if page.audit_log_entries.len() < AUDIT_LOG_PAGE_SIZE as usize {
+            self.finished = true;
+        }
+        if let Some(last) = page.audit_log_entries.last() {
+            self.params.before = Some(last.id);
+        }
+        self.users.extend(page.users.into_iter().map(|x| (x.id, x)));
+        self.webhooks.extend(page.webhooks.into_iter().map(|x| (x.id, x)));
+        self.integrations.extend(page.integrations.into_iter().map(|x| (x.id, x)));
+        self.entries.extend(page.audit_log_entries);
+    }
+}
+impl <'a> Stream for AuditLogStream<'a> {
+    type Item = Result<AuditLogEntry>;
+    fn poll_next(
+        mut self: Pin<&mut Self>, cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<AuditLogEntry>>> {
+        let this = &mut *self;
+        loop {
+            if let Some(entry) = this.entries.pop_front() {
+                return Poll::Ready(Some(Ok(entry)))
+            }
+            if this.finished {
+                return Poll::Ready(None)
+            }
+            let ops = &this.ops;
+            let params = &this.params;
+            let fut = this.fut.get_or_insert_with(|| {
+                ops.raw.clone().get_guild_audit_log(ops.id, params.clone()).boxed()
+            });
+            let result = match fut.poll_unpin(cx) {
+                Poll::Ready(result) => result,
+                Poll::Pending => return Poll::Pending,
+            };
+            this.fut = None;
+            match result {
+                Ok(page) => this.push_page(page),
+                Err(e) => {
+                    this.finished = true;
+                    return Poll::Ready(Some(Err(e)))
+                }
+            }
+        }
+    }
+}
+
 /// Performs operations relating to guild members.
 ///
 /// Instances can be obtained by calling [`GuildOps::member`] or
@@ -152,12 +390,42 @@ impl <'a> MemberOps<'a> {
         Ok(())
     }
 
+    /// Sets the member's roles, replacing all roles they currently have.
+    ///
+    /// This is not recommended as there is the possibility of a race condition between your bot
+    /// and another bot. See [`ModifyGuildMemberFut::roles`] for more information.
+    pub async fn set_roles(self, roles: impl Into<Cow<'a, [RoleId]>>) -> Result<()> {
+        let mut params = ModifyGuildMemberParams::default();
+        params.roles = Some(roles.into());
+        self.raw.modify_guild_member(self.guild_id, self.user_id, params).await
+    }
+
+    /// Times out this member, preventing them from communicating in the guild until the given
+    /// time.
+    pub async fn timeout(self, until: DateTime<Utc>) -> Result<()> {
+        let mut params = ModifyGuildMemberParams::default();
+        params.communication_disabled_until = Some(Some(until));
+        self.raw.modify_guild_member(self.guild_id, self.user_id, params).await
+    }
+
+    /// Removes this member's timeout.
+    pub async fn remove_timeout(self) -> Result<()> {
+        let mut params = ModifyGuildMemberParams::default();
+        params.communication_disabled_until = Some(None);
+        self.raw.modify_guild_member(self.guild_id, self.user_id, params).await
+    }
+
     /// Kicks this member from the guild.
     pub async fn kick(self) -> Result<()> {
         self.raw.remove_guild_member(self.guild_id, self.user_id).await
     }
 
-    // TODO: Ban
+    /// Bans this user from the guild.
+    ///
+    /// For information on what properties can be set, see the methods of [`BanFut`].
+    pub fn ban(self) -> BanFut<'a> {
+        BanFut::new(self)
+    }
 
     /// Unbans a user from the guild.
     pub async fn unban(self) -> Result<()> {
@@ -169,15 +437,15 @@ impl <'a> MemberOps<'a> {
 
 fn check_is_image(image: &ImageData) -> Result<()> {
     match image.format() {
-        ImageFormat::Png | ImageFormat::Jpeg => { }
-        _ => bail!(InvalidInput, "Image must be PNG or JPEG."),
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::WebP => { }
+        _ => bail!(InvalidInput, "Image must be PNG, JPEG or WebP."),
     }
     Ok(())
 }
 fn check_is_anim_image(image: &ImageData) -> Result<()> {
     match image.format() {
-        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif => { }
-        _ => bail!(InvalidInput, "Image must be GIF, PNG or JPEG."),
+        ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Gif | ImageFormat::WebP => { }
+        _ => bail!(InvalidInput, "Image must be GIF, PNG, JPEG or WebP."),
     }
     Ok(())
 }
@@ -267,6 +535,89 @@ fut_builder! {
     }
 }
 
+fut_builder! {
+    ('a, reorder_channels_mod, GuildOps, self)
+
+    /// A future for changing the positions of channels in a guild.
+    ///
+    /// Positions do not need to be contiguous. Discord sorts channels by their position, and
+    /// then by their ID, so positions may be sparse, and only the channels being moved need to
+    /// be included.
+    ///
+    /// Instances can be obtained via [`GuildOps::reorder_channels`].
+    struct ReorderChannelsFut {
+        params: Vec<ModifyGuildChannelPositionParams>,
+        phantom: PhantomData<&'a ()>,
+    }
+    into_async!(|ops, data| -> Result<()> {
+        ensure!(!data.params.is_empty(), InvalidInput, "No channels were moved.");
+        ops.raw.modify_guild_channel_position(ops.id, data.params).await
+    });
+
+    /// Moves a channel to a new position.
+    pub fn move_channel(&mut self, id: impl Into<ChannelId>, position: u32) {
+        self.params.push(ModifyGuildChannelPositionParams::new(id, position));
+    }
+
+    /// Moves a channel to a new position within a new parent category, or out of its category if
+    /// `parent` is `None`.
+    ///
+    /// If `lock_permissions` is set, the channel's permission overwrites are synced with the new
+    /// category.
+    pub fn move_channel_to_category(
+        &mut self,
+        id: impl Into<ChannelId>,
+        position: u32,
+        parent: Option<ChannelId>,
+        lock_permissions: bool,
+    ) {
+        self.params.push(
+            ModifyGuildChannelPositionParams::new(id, position)
+                .parent_id(parent)
+                .lock_permissions(lock_permissions)
+        );
+    }
+}
+
+fut_builder! {
+    ('a, create_role_mod, GuildOps, self)
+
+    /// A future for creating a new role in a guild.
+    ///
+    /// Instances can be obtained via [`GuildOps::create_role`].
+    struct CreateRoleFut {
+        params: GuildRoleParams<'a>,
+    }
+    into_async!(|ops, data| -> Result<Role> {
+        ops.raw.create_guild_role(ops.id, data.params).await
+    });
+
+    /// Sets the name of the role.
+    pub fn name(&mut self, name: impl Into<Cow<'a, str>>) {
+        self.params.name = Some(name.into());
+    }
+
+    /// Sets the permissions granted by the role.
+    pub fn permissions(&mut self, permissions: impl Into<EnumSet<Permission>>) {
+        self.params.permissions = Some(permissions.into());
+    }
+
+    /// Sets the color of the role.
+    pub fn color(&mut self, color: impl Into<Color>) {
+        self.params.color = Some(color.into());
+    }
+
+    /// Sets whether the role is displayed separately in the member list.
+    pub fn hoist(&mut self, hoist: bool) {
+        self.params.hoist = Some(hoist);
+    }
+
+    /// Sets whether the role can be mentioned by anyone.
+    pub fn mentionable(&mut self, mentionable: bool) {
+        self.params.mentionable = Some(mentionable);
+    }
+}
+
 fut_builder! {
     ('a, modify_guild_member_mod, MemberOps, self)
 
@@ -337,5 +688,41 @@ fut_builder! {
     pub fn disconnect_voice(&mut self) {
         self.params.channel_id = Some(None);
     }
+
+    /// Times out the user until the given time.
+    pub fn timeout_until(&mut self, until: DateTime<Utc>) {
+        self.params.communication_disabled_until = Some(Some(until));
+    }
+
+    /// Removes the user's timeout.
+    pub fn remove_timeout(&mut self) {
+        self.params.communication_disabled_until = Some(None);
+    }
 }
 
+fut_builder! {
+    ('a, ban_fut_mod, MemberOps, self)
+
+    /// A future for banning a user from a guild.
+    ///
+    /// The reason for the ban can be set with the `reason` method, and will be recorded in
+    /// the audit log.
+    ///
+    /// Instances can be obtained via [`MemberOps::ban`] or [`GuildOps::ban`].
+    struct BanFut {
+        params: CreateGuildBanParams<'a>,
+    }
+    into_async!(|ops, data| -> Result<()> {
+        if let Some(days) = data.params.delete_message_days {
+            ensure!(days <= 7, InvalidInput, "Can only delete up to 7 days of messages.");
+        }
+        ops.raw.create_guild_ban(ops.guild_id, ops.user_id, data.params).await
+    });
+
+    /// Sets the number of days of the user's messages to delete.
+    ///
+    /// Currently limited to 0-7 days.
+    pub fn delete_message_days(&mut self, days: u32) {
+        self.params.delete_message_days = Some(days);
+    }
+}
@@ -0,0 +1,40 @@
+use crate::http::*;
+use minnie_errors::*;
+use minnie_model::channel::*;
+use minnie_model::types::*;
+
+/// Performs operations relating to invites.
+///
+/// Instances can be obtained by calling
+/// [`DiscordContext::invite`](`crate::DiscordContext::invite`).
+#[derive(Debug, Clone)]
+pub struct InviteOps<'a> {
+    pub(crate) code: String,
+    pub(crate) params: GetInviteParams<'static>,
+    pub(crate) raw: Routes<'a>,
+}
+impl <'a> InviteOps<'a> {
+    /// Sets whether to include approximate member and presence counts when retrieving the invite.
+    pub fn with_counts(mut self, with_counts: bool) -> Self {
+        self.params.with_counts = Some(with_counts);
+        self
+    }
+
+    /// Sets whether to include the expiration date when retrieving the invite.
+    pub fn with_expiration(mut self, with_expiration: bool) -> Self {
+        self.params.with_expiration = Some(with_expiration);
+        self
+    }
+
+    /// Retrieves information relating to the invite.
+    pub async fn get(self) -> Result<Invite> {
+        self.raw.get_invite(&self.code, self.params).await
+    }
+
+    /// Deletes the invite.
+    pub async fn delete(self) -> Result<Invite> {
+        self.raw.delete_invite(&self.code).await
+    }
+
+    routes_wrapper!(self, &mut self.raw);
+}
@@ -4,6 +4,7 @@
 //! methods on [`DiscordContext`].
 
 use crate::context::*;
+use crate::http::GetInviteParams;
 use minnie_model::types::*;
 
 // TODO: Create iterators based on the various get_* functions.
@@ -163,10 +164,12 @@ macro_rules! fut_builder {
 
 mod channel;
 mod guild;
+mod invite;
 mod user;
 
 pub use channel::*;
 pub use guild::*;
+pub use invite::*;
 pub use user::*;
 
 impl DiscordContext {
@@ -191,4 +194,9 @@ impl DiscordContext {
     pub fn member(&self, guild: impl Into<GuildId>, member: impl Into<UserId>) -> MemberOps<'_> {
         MemberOps { guild_id: guild.into(), user_id: member.into(), raw: self.raw() }
     }
+
+    /// Performs operations relating to an invite.
+    pub fn invite(&self, code: impl Into<String>) -> InviteOps<'_> {
+        InviteOps { code: code.into(), params: GetInviteParams::new(), raw: self.raw() }
+    }
 }
\ No newline at end of file
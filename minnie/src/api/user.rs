@@ -0,0 +1,73 @@
+use crate::api::ChannelOps;
+use crate::context::DiscordContext;
+use crate::http::*;
+use minnie_errors::*;
+use minnie_model::guild::*;
+use minnie_model::types::*;
+
+/// The number of guilds requested at once by [`DiscordContext::get_guilds`].
+const GUILDS_PAGE_SIZE: u32 = 100;
+
+impl DiscordContext {
+    /// Retrieves every guild the current user is a member of.
+    ///
+    /// If `with_counts` is set, the approximate member and presence counts of each guild are
+    /// included. Guilds are requested 100 at a time, so this makes one API call per 100 guilds.
+    pub async fn get_guilds(&self, with_counts: bool) -> Result<Vec<PartialGuild>> {
+        let mut guilds: Vec<PartialGuild> = Vec::new();
+        loop {
+            let mut params = GetCurrentUserGuildsParams::new()
+                .limit(GUILDS_PAGE_SIZE)
+                .with_counts(with_counts);
+            if let Some(last) = guilds.last() {
+                params = params.after(last.id);
+            }
+            let page = self.raw().get_current_user_guilds(params).await?;
+            let finished = page.len() < GUILDS_PAGE_SIZE as usize;
+            guilds.extend(page);
+            if finished {
+                return Ok(guilds)
+            }
+        }
+    }
+
+    /// Performs operations relating to the DM channel with a user.
+    ///
+    /// The DM channel is created the first time this is called for a user. Its ID is then cached
+    /// and reused for later calls, so this only makes an API call once per user.
+    ///
+    /// If sending to the channel later fails with an `Unknown Channel` error, the cached ID can
+    /// be cleared with [`DiscordContext::invalidate_dm`] so the next call retrieves it again.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::types::UserId;
+    /// async fn greet(ctx: DiscordContext, user: UserId) -> Result<()> {
+    ///     ctx.dm(user).await?.post().content("hi").await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn dm(&self, user: impl Into<UserId>) -> Result<ChannelOps<'_>> {
+        let user = user.into();
+        let cached = self.data.dm_channels.read().get(&user).cloned();
+        let id = match cached {
+            Some(id) => id,
+            None => {
+                let id = self.raw().create_dm(user).await?.id;
+                self.data.dm_channels.write().insert(user, id);
+                id
+            }
+        };
+        Ok(self.channel(id))
+    }
+
+    /// Clears the cached DM channel for a user, if one exists.
+    ///
+    /// See [`DiscordContext::dm`] for details.
+    pub fn invalidate_dm(&self, user: impl Into<UserId>) {
+        self.data.dm_channels.write().remove(&user.into());
+    }
+}
@@ -0,0 +1,375 @@
+//! A blocking wrapper around the asynchronous Discord API.
+//!
+//! This module is only available when the `blocking` feature is enabled. It is intended for
+//! simple scripts and tools that do not otherwise use an asynchronous runtime.
+//!
+//! [`BlockingDiscordContext`] provides blocking versions of the operations available on a
+//! [`DiscordContext`]. Each of them resolves the same future as its asynchronous counterpart, and
+//! returns its result directly:
+//!
+//! ```rust,no_run
+//! # use minnie::Result;
+//! # use minnie::blocking::BlockingDiscordContext;
+//! # use minnie::model::channel::Channel;
+//! # use minnie::model::types::{ChannelId, DiscordToken};
+//! fn get_channel(token: DiscordToken, id: ChannelId) -> Result<Channel> {
+//!     let ctx = BlockingDiscordContext::new(token)?;
+//!     ctx.channel(id).get()
+//! }
+//! ```
+//!
+//! Operations that return a builder on the asynchronous API instead take a closure that
+//! configures the builder before the API call is made:
+//!
+//! ```rust,no_run
+//! # use minnie::Result;
+//! # use minnie::blocking::BlockingDiscordContext;
+//! # use minnie::model::types::ChannelId;
+//! fn say_hello(ctx: &BlockingDiscordContext, id: ChannelId) -> Result<()> {
+//!     ctx.channel(id).post(|post| post.content("Hello!"))?;
+//!     Ok(())
+//! }
+//! ```
+//!
+//! Anything not covered by these wrappers can still be done with the asynchronous API through
+//! [`BlockingDiscordContext::block_on`] or [`BlockingDiscordContext::run`].
+//!
+//! The methods of this type must not be called from within an asynchronous context, as they
+//! block the current thread until the call completes.
+
+use chrono::{DateTime, Utc};
+use crate::api::*;
+use crate::context::*;
+use crate::http::ModifyGuildRolePositionParams;
+use enumset::*;
+use futures::stream::{Stream, StreamExt};
+use minnie_errors::*;
+use minnie_model::channel::*;
+use minnie_model::guild::*;
+use minnie_model::message::*;
+use minnie_model::types::*;
+use std::borrow::Cow;
+use std::future::Future;
+use tokio::runtime::{Builder, Handle, Runtime};
+
+/// A Discord context that performs API calls synchronously.
+///
+/// Each blocking context owns an internal tokio runtime that drives the HTTP client and any
+/// gateway connections started through [`BlockingDiscordContext::handle`].
+#[derive(Debug)]
+pub struct BlockingDiscordContext {
+    ctx: DiscordContext,
+    runtime: Runtime,
+}
+impl BlockingDiscordContext {
+    /// Creates a new blocking Discord context using the default settings.
+    pub fn new(client_token: DiscordToken) -> Result<Self> {
+        let runtime = Self::make_runtime()?;
+        let ctx = runtime.enter(|| DiscordContext::new(client_token))?;
+        Ok(BlockingDiscordContext { ctx, runtime })
+    }
+
+    /// Wraps an existing Discord context, such as one created with a custom
+    /// [`DiscordContextBuilder`].
+    pub fn from_context(ctx: DiscordContext) -> Result<Self> {
+        Ok(BlockingDiscordContext { ctx, runtime: Self::make_runtime()? })
+    }
+
+    fn make_runtime() -> Result<Runtime> {
+        Builder::new()
+            .threaded_scheduler()
+            .enable_all()
+            .thread_name("minnie-blocking")
+            .build()
+            .internal_err("Could not start tokio runtime.")
+    }
+
+    /// Returns the underlying asynchronous Discord context.
+    pub fn context(&self) -> &DiscordContext {
+        &self.ctx
+    }
+
+    /// Returns a handle to the internal runtime, for use with
+    /// [`GatewayController::connect`](`crate::gateway::GatewayController::connect`).
+    pub fn handle(&self) -> &Handle {
+        self.runtime.handle()
+    }
+
+    /// Blocks the current thread until the given future completes, and returns its result.
+    ///
+    /// This is typically used with the futures returned by the methods of [`DiscordContext`].
+    pub fn block_on<F: Future>(&self, fut: F) -> F::Output {
+        self.runtime.enter(|| futures::executor::block_on(fut))
+    }
+
+    /// Calls a function with the underlying Discord context, and blocks on the future it returns.
+    ///
+    /// This is a convenience method for `ctx.block_on(func(ctx.context()))`.
+    pub fn run<'a, F: Future>(&'a self, func: impl FnOnce(&'a DiscordContext) -> F) -> F::Output {
+        self.block_on(func(&self.ctx))
+    }
+
+    /// Performs operations relating to a Discord channel.
+    pub fn channel(&self, id: impl Into<ChannelId>) -> BlockingChannelOps<'_> {
+        BlockingChannelOps { ctx: self, ops: self.ctx.channel(id) }
+    }
+
+    /// Performs operations relating to a message.
+    pub fn message(
+        &self, channel: impl Into<ChannelId>, message: impl Into<MessageId>,
+    ) -> BlockingMessageOps<'_> {
+        BlockingMessageOps { ctx: self, ops: self.ctx.message(channel, message) }
+    }
+
+    /// Performs operations relating to a guild.
+    pub fn guild(&self, id: impl Into<GuildId>) -> BlockingGuildOps<'_> {
+        BlockingGuildOps { ctx: self, ops: self.ctx.guild(id) }
+    }
+
+    /// Performs operations relating to a member.
+    pub fn member(
+        &self, guild: impl Into<GuildId>, member: impl Into<UserId>,
+    ) -> BlockingMemberOps<'_> {
+        BlockingMemberOps { ctx: self, ops: self.ctx.member(guild, member) }
+    }
+
+    /// Performs operations relating to an invite.
+    pub fn invite(&self, code: impl Into<String>) -> BlockingInviteOps<'_> {
+        BlockingInviteOps { ctx: self, ops: self.ctx.invite(code) }
+    }
+
+    /// Retrieves every guild the current user is a member of.
+    ///
+    /// See [`DiscordContext::get_guilds`] for details.
+    pub fn get_guilds(&self, with_counts: bool) -> Result<Vec<PartialGuild>> {
+        self.block_on(self.ctx.get_guilds(with_counts))
+    }
+
+    /// Performs operations relating to the DM channel with a user.
+    ///
+    /// See [`DiscordContext::dm`] for details.
+    pub fn dm(&self, user: impl Into<UserId>) -> Result<BlockingChannelOps<'_>> {
+        Ok(BlockingChannelOps { ctx: self, ops: self.block_on(self.ctx.dm(user))? })
+    }
+}
+
+macro_rules! blocking_ops {
+    (
+        $(#[$struct_meta:meta])*
+        struct $name:ident($ops:ident);
+        $(
+            $(#[$fn_meta:meta])*
+            fn $fn_name:ident($($param_name:ident: $param_ty:ty),* $(,)?) -> $ret:ty;
+        )*
+        $(
+            builder fn $builder_name:ident(
+                $($builder_param_name:ident: $builder_param_ty:ty),* $(,)?
+            ) -> $builder_ty:ident;
+        )*
+    ) => {
+        $(#[$struct_meta])*
+        #[derive(Debug, Clone)]
+        pub struct $name<'a> {
+            ctx: &'a BlockingDiscordContext,
+            ops: $ops<'a>,
+        }
+        impl <'a> $name<'a> {
+            /// Makes an API call using a Bearer token.
+            pub fn bearer_token(self, token: DiscordBearerToken) -> Self {
+                $name { ctx: self.ctx, ops: self.ops.bearer_token(token) }
+            }
+
+            /// Sets the reason for the API call. This is recorded in the audit log for many calls.
+            pub fn reason(self, reason: impl Into<String>) -> Self {
+                $name { ctx: self.ctx, ops: self.ops.reason(reason) }
+            }
+
+            $(
+                $(#[$fn_meta])*
+                #[doc = concat!(
+                    "Blocking version of [`", stringify!($ops), "::", stringify!($fn_name), "`].",
+                )]
+                pub fn $fn_name(self, $($param_name: $param_ty,)*) -> $ret {
+                    self.ctx.block_on(self.ops.$fn_name($($param_name,)*))
+                }
+            )*
+
+            $(
+                #[doc = concat!(
+                    "Blocking version of [`", stringify!($ops), "::", stringify!($builder_name),
+                    "`].\n\nThe [`", stringify!($builder_ty), "`] is configured by calling \
+                     `build` before the API call is made.",
+                )]
+                pub fn $builder_name(
+                    self, $($builder_param_name: $builder_param_ty,)*
+                    build: impl FnOnce($builder_ty<'a>) -> $builder_ty<'a>,
+                ) -> <$builder_ty<'a> as Future>::Output {
+                    self.ctx.block_on(build(self.ops.$builder_name($($builder_param_name,)*)))
+                }
+            )*
+        }
+    };
+}
+
+blocking_ops! {
+    /// Performs blocking operations relating to a Discord channel.
+    ///
+    /// Instances can be obtained by calling [`BlockingDiscordContext::channel`].
+    struct BlockingChannelOps(ChannelOps);
+
+    fn get() -> Result<Channel>;
+    fn delete() -> Result<Channel>;
+    fn delete_messages(messages: impl Into<Cow<'a, [MessageId]>>) -> Result<()>;
+    fn set_permissions(
+        overwrite: impl Into<PermissionOverwriteId>,
+        allow: impl Into<EnumSet<Permission>>, deny: impl Into<EnumSet<Permission>>,
+    ) -> Result<()>;
+    fn get_invites() -> Result<Vec<InviteWithMetadata>>;
+    fn clear_permissions(overwrite: impl Into<PermissionOverwriteId>) -> Result<()>;
+    fn typing() -> Result<()>;
+    fn get_pinned_messages() -> Result<Vec<Message>>;
+
+    builder fn modify() -> ModifyChannelFut;
+    builder fn get_message_history() -> GetMessageHistoryFut;
+    builder fn post() -> PostFut;
+    builder fn invite() -> InviteFut;
+}
+impl <'a> BlockingChannelOps<'a> {
+    /// Performs operations relating to a message.
+    pub fn message(self, id: impl Into<MessageId>) -> BlockingMessageOps<'a> {
+        BlockingMessageOps { ctx: self.ctx, ops: self.ctx.block_on(self.ops.message(id)) }
+    }
+}
+
+blocking_ops! {
+    /// Performs blocking operations relating to a message.
+    ///
+    /// Instances can be obtained by calling [`BlockingDiscordContext::message`] or
+    /// [`BlockingChannelOps::message`].
+    struct BlockingMessageOps(MessageOps);
+
+    fn get() -> Result<Message>;
+    fn referenced() -> Result<Option<Message>>;
+    fn suppress_embeds(suppress: bool) -> Result<Message>;
+    fn react(emoji: impl Into<EmojiRef>) -> Result<()>;
+    fn react_all(emojis: impl IntoIterator<Item = EmojiRef>) -> Result<()>;
+    fn delete_own_reaction(emoji: impl Into<EmojiRef>) -> Result<()>;
+    fn delete_user_reaction(emoji: impl Into<EmojiRef>, user: impl Into<UserId>) -> Result<()>;
+    fn delete_all_reactions() -> Result<()>;
+    fn delete_reactions_for_emoji(emoji: impl Into<EmojiRef>) -> Result<()>;
+    fn delete() -> Result<()>;
+    fn pin() -> Result<()>;
+    fn unpin() -> Result<()>;
+
+    builder fn emoji_reactions(emoji: &'a EmojiRef) -> EmojiReactionsFut;
+    builder fn edit() -> EditFut;
+}
+
+blocking_ops! {
+    /// Performs blocking operations relating to guilds.
+    ///
+    /// Instances can be obtained by calling [`BlockingDiscordContext::guild`].
+    struct BlockingGuildOps(GuildOps);
+
+    fn get() -> Result<Guild>;
+    fn get_with_counts() -> Result<Guild>;
+    fn delete() -> Result<()>;
+    fn get_channels() -> Result<Vec<Channel>>;
+    fn channels_by_category() -> Result<Vec<(Option<Channel>, Vec<Channel>)>>;
+    fn bans() -> Result<Vec<GuildBan>>;
+    fn get_ban(user: impl Into<UserId>) -> Result<GuildBan>;
+    fn unban(user: impl Into<UserId>) -> Result<()>;
+    fn change_nick(nick: impl AsRef<str>) -> Result<()>;
+    fn get_roles() -> Result<Vec<Role>>;
+    fn set_role_positions(positions: Vec<ModifyGuildRolePositionParams>) -> Result<()>;
+    fn get_voice_regions() -> Result<Vec<VoiceRegion>>;
+    fn get_invites() -> Result<Vec<InviteWithMetadata>>;
+    fn get_embed_settings() -> Result<GuildEmbedSettings>;
+    fn get_vanity_url() -> Result<Option<String>>;
+
+    builder fn modify() -> ModifyGuildFut;
+    builder fn reorder_channels() -> ReorderChannelsFut;
+    builder fn ban(user: impl Into<UserId>) -> BanFut;
+    builder fn create_role() -> CreateRoleFut;
+}
+impl <'a> BlockingGuildOps<'a> {
+    /// Performs operations related to a guild member.
+    pub fn member(self, id: impl Into<UserId>) -> BlockingMemberOps<'a> {
+        BlockingMemberOps { ctx: self.ctx, ops: self.ops.member(id) }
+    }
+
+    /// Returns an iterator over the entries in this guild's audit log, from newest to oldest.
+    ///
+    /// The [`AuditLogStream`] is configured by calling `build` before any API calls are made.
+    /// See [`GuildOps::audit_log_iter`] for details.
+    pub fn audit_log_iter(
+        self, build: impl FnOnce(AuditLogStream<'a>) -> AuditLogStream<'a>,
+    ) -> BlockingIter<'a, AuditLogStream<'a>> {
+        BlockingIter { ctx: self.ctx, stream: build(self.ops.audit_log_iter()) }
+    }
+}
+
+blocking_ops! {
+    /// Performs blocking operations relating to guild members.
+    ///
+    /// Instances can be obtained by calling [`BlockingDiscordContext::member`] or
+    /// [`BlockingGuildOps::member`].
+    struct BlockingMemberOps(MemberOps);
+
+    fn get() -> Result<Member>;
+    fn add_role(role: impl Into<RoleId>) -> Result<()>;
+    fn add_roles(roles: impl IntoIterator<Item = impl Into<RoleId>>) -> Result<()>;
+    fn remove_role(role: impl Into<RoleId>) -> Result<()>;
+    fn remove_roles(roles: impl IntoIterator<Item = impl Into<RoleId>>) -> Result<()>;
+    fn set_roles(roles: impl Into<Cow<'a, [RoleId]>>) -> Result<()>;
+    fn timeout(until: DateTime<Utc>) -> Result<()>;
+    fn remove_timeout() -> Result<()>;
+    fn kick() -> Result<()>;
+    fn unban() -> Result<()>;
+
+    builder fn modify() -> ModifyGuildMemberFut;
+    builder fn ban() -> BanFut;
+}
+
+blocking_ops! {
+    /// Performs blocking operations relating to invites.
+    ///
+    /// Instances can be obtained by calling [`BlockingDiscordContext::invite`].
+    struct BlockingInviteOps(InviteOps);
+
+    fn get() -> Result<Invite>;
+    fn delete() -> Result<Invite>;
+}
+impl <'a> BlockingInviteOps<'a> {
+    /// Sets whether to include approximate member and presence counts when retrieving the invite.
+    pub fn with_counts(self, with_counts: bool) -> Self {
+        BlockingInviteOps { ctx: self.ctx, ops: self.ops.with_counts(with_counts) }
+    }
+
+    /// Sets whether to include the expiration date when retrieving the invite.
+    pub fn with_expiration(self, with_expiration: bool) -> Self {
+        BlockingInviteOps { ctx: self.ctx, ops: self.ops.with_expiration(with_expiration) }
+    }
+}
+
+/// An iterator that blocks on each item of a stream.
+///
+/// Instances can be obtained via [`BlockingGuildOps::audit_log_iter`].
+pub struct BlockingIter<'a, S> {
+    ctx: &'a BlockingDiscordContext,
+    stream: S,
+}
+impl <'a, S> BlockingIter<'a, S> {
+    /// Returns the underlying stream.
+    ///
+    /// This can be used to access methods such as [`AuditLogStream::get_user`].
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+}
+impl <'a, S: Stream + Unpin> Iterator for BlockingIter<'a, S> {
+    type Item = S::Item;
+    fn next(&mut self) -> Option<S::Item> {
+        self.ctx.block_on(self.stream.next())
+    }
+}
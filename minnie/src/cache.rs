@@ -0,0 +1,368 @@
+//! Maintains a local cache of guilds, channels, roles, and members.
+//!
+//! This module is only available when the `cache` feature is enabled. The cache is updated from
+//! gateway events before they are passed to the [`GatewayHandler`], so it is already up to date
+//! when an event is handled.
+//!
+//! Only events that the handler receives are used to update the cache. Guilds, channels and roles
+//! are only cached if the handler requests the [`GatewayIntent::Guilds`] intent, and members are
+//! only cached if it requests the [`GatewayIntent::GuildMembers`] intent, as the cache could not
+//! otherwise be kept up to date. Likewise, voice states are only cached with the
+//! [`GatewayIntent::GuildVoiceStates`] intent. Events ignored with
+//! [`GatewayHandler::ignores_event`] are not seen by the cache either.
+//!
+//! When a shard starts a new session rather than resuming one, the guilds it handles are removed
+//! from the cache, as Discord sends each of them again in a `Guild Create` event.
+//!
+//! [`GatewayHandler`]: crate::gateway::GatewayHandler
+//! [`GatewayHandler::ignores_event`]: crate::gateway::GatewayHandler::ignores_event
+
+use crate::context::DiscordContext;
+use enumset::EnumSet;
+use fxhash::{FxHashMap, FxHashSet};
+use minnie_model::channel::Channel;
+use minnie_model::event::*;
+use minnie_model::guild::{Guild, Member, Role, VoiceState};
+use minnie_model::types::*;
+use parking_lot::RwLock;
+use std::mem;
+
+#[derive(Debug)]
+struct CachedGuild {
+    /// The guild, with its `roles`, `channels`, and `members` fields cleared.
+    guild: Guild,
+    channels: FxHashSet<ChannelId>,
+    roles: FxHashSet<RoleId>,
+    members: FxHashMap<UserId, Member>,
+}
+
+#[derive(Debug, Default)]
+struct CacheData {
+    intents: EnumSet<GatewayIntent>,
+    guilds: FxHashMap<GuildId, CachedGuild>,
+    channels: FxHashMap<ChannelId, Channel>,
+    roles: FxHashMap<RoleId, Role>,
+}
+impl CacheData {
+    fn caches_guilds(&self) -> bool {
+        self.intents.contains(GatewayIntent::Guilds)
+    }
+    fn caches_members(&self) -> bool {
+        self.intents.contains(GatewayIntent::GuildMembers)
+    }
+    fn caches_voice_states(&self) -> bool {
+        self.intents.contains(GatewayIntent::GuildVoiceStates)
+    }
+
+    fn insert_guild(&mut self, mut guild: Guild) {
+        self.remove_guild(guild.id);
+
+        let channels = mem::take(&mut guild.channels);
+        let roles = mem::take(&mut guild.roles);
+        let members = mem::take(&mut guild.members);
+        if !self.caches_voice_states() {
+            guild.voice_states.clear();
+        }
+        let mut cached = CachedGuild {
+            guild,
+            channels: Default::default(),
+            roles: Default::default(),
+            members: Default::default(),
+        };
+        for mut channel in channels {
+            channel.guild_id = Some(cached.guild.id);
+            cached.channels.insert(channel.id);
+            self.channels.insert(channel.id, channel);
+        }
+        for role in roles {
+            cached.roles.insert(role.id);
+            self.roles.insert(role.id, role);
+        }
+        if self.caches_members() {
+            for member in members {
+                cached.members.insert(member.user.id, member);
+            }
+        }
+        self.guilds.insert(cached.guild.id, cached);
+    }
+    fn update_guild(&mut self, mut guild: Guild) {
+        let old = match self.guilds.get_mut(&guild.id) {
+            Some(old) => old,
+            None => return,
+        };
+        for role in &old.roles {
+            self.roles.remove(role);
+        }
+        old.roles.clear();
+        for role in guild.roles.drain(..) {
+            old.roles.insert(role.id);
+            self.roles.insert(role.id, role);
+        }
+        guild.channels.clear();
+        guild.members.clear();
+        guild.member_count = guild.member_count.or(old.guild.member_count);
+        guild.voice_states = mem::take(&mut old.guild.voice_states);
+        old.guild = guild;
+    }
+    fn remove_guild(&mut self, id: GuildId) {
+        if let Some(old) = self.guilds.remove(&id) {
+            for channel in &old.channels {
+                self.channels.remove(channel);
+            }
+            for role in &old.roles {
+                self.roles.remove(role);
+            }
+        }
+    }
+
+    /// Removes the guilds handled by a shard, as a new session sends all of them again.
+    fn remove_shard_guilds(&mut self, shard: Option<ShardId>) {
+        let ids: Vec<GuildId> = self.guilds.keys()
+            .filter(|&&id| shard.map_or(true, |shard| shard.handles_guild(id)))
+            .copied()
+            .collect();
+        for id in ids {
+            self.remove_guild(id);
+        }
+    }
+
+    fn insert_channel(&mut self, channel: Channel) {
+        if let Some(guild_id) = channel.guild_id {
+            match self.guilds.get_mut(&guild_id) {
+                Some(guild) => guild.channels.insert(channel.id),
+                None => return,
+            };
+        }
+        self.channels.insert(channel.id, channel);
+    }
+    fn remove_channel(&mut self, channel: &Channel) {
+        if let Some(guild_id) = channel.guild_id {
+            if let Some(guild) = self.guilds.get_mut(&guild_id) {
+                guild.channels.remove(&channel.id);
+            }
+        }
+        self.channels.remove(&channel.id);
+    }
+
+    fn insert_role(&mut self, guild_id: GuildId, role: Role) {
+        if let Some(guild) = self.guilds.get_mut(&guild_id) {
+            guild.roles.insert(role.id);
+            self.roles.insert(role.id, role);
+        }
+    }
+    fn remove_role(&mut self, guild_id: GuildId, role: RoleId) {
+        if let Some(guild) = self.guilds.get_mut(&guild_id) {
+            guild.roles.remove(&role);
+            self.roles.remove(&role);
+        }
+    }
+
+    fn update(&mut self, event: &GatewayEvent) {
+        if self.caches_guilds() {
+            match event {
+                GatewayEvent::Ready(ev) => self.remove_shard_guilds(ev.shard),
+                GatewayEvent::GuildCreate(ev) => self.insert_guild(ev.0.clone()),
+                GatewayEvent::GuildUpdate(ev) => self.update_guild(ev.0.clone()),
+                GatewayEvent::GuildDelete(ev) if ev.0.unavailable => {
+                    if let Some(guild) = self.guilds.get_mut(&ev.0.id) {
+                        guild.guild.unavailable = true;
+                    }
+                }
+                GatewayEvent::GuildDelete(ev) => self.remove_guild(ev.0.id),
+                GatewayEvent::ChannelCreate(ev) => self.insert_channel(ev.0.clone()),
+                GatewayEvent::ChannelUpdate(ev) => self.insert_channel(ev.0.clone()),
+                GatewayEvent::ChannelDelete(ev) => self.remove_channel(&ev.0),
+                GatewayEvent::GuildRoleCreate(ev) => self.insert_role(ev.guild_id, ev.role.clone()),
+                GatewayEvent::GuildRoleUpdate(ev) => self.insert_role(ev.guild_id, ev.role.clone()),
+                GatewayEvent::GuildRoleDelete(ev) => self.remove_role(ev.guild_id, ev.role_id),
+                _ => { }
+            }
+        }
+        if self.caches_members() {
+            self.update_members(event);
+        }
+        if self.caches_voice_states() {
+            if let GatewayEvent::VoiceStateUpdate(ev) = event {
+                self.update_voice_state(&ev.0);
+            }
+        }
+    }
+
+    fn update_voice_state(&mut self, state: &VoiceState) {
+        let guild = state.guild_id.and_then(|x| self.guilds.get_mut(&x));
+        if let Some(guild) = guild {
+            let states = &mut guild.guild.voice_states;
+            states.retain(|x| x.user_id != state.user_id);
+            if state.channel_id.is_some() {
+                states.push(state.clone());
+            }
+        }
+    }
+
+    fn update_members(&mut self, event: &GatewayEvent) {
+        match event {
+            GatewayEvent::GuildMemberAdd(ev) => {
+                if let Some(guild) = self.guilds.get_mut(&ev.guild_id) {
+                    guild.members.insert(ev.member.user.id, ev.member.clone());
+                    if let Some(count) = &mut guild.guild.member_count {
+                        *count += 1;
+                    }
+                }
+            }
+            GatewayEvent::GuildMemberRemove(ev) => {
+                if let Some(guild) = self.guilds.get_mut(&ev.guild_id) {
+                    guild.members.remove(&ev.user.id);
+                    if let Some(count) = &mut guild.guild.member_count {
+                        *count = count.saturating_sub(1);
+                    }
+                }
+            }
+            GatewayEvent::GuildMemberUpdate(ev) => {
+                let guild = self.guilds.get_mut(&ev.guild_id);
+                if let Some(member) = guild.and_then(|x| x.members.get_mut(&ev.user.id)) {
+                    member.user = ev.user.clone();
+                    member.info.nick = ev.nick.clone();
+                    member.info.roles = ev.roles.clone();
+                    member.info.premium_since = ev.premium_since;
+                }
+            }
+            GatewayEvent::GuildMembersChunk(ev) => {
+                if let Some(guild) = self.guilds.get_mut(&ev.guild_id) {
+                    for member in &ev.members {
+                        guild.members.insert(member.user.id, member.clone());
+                    }
+                }
+            }
+            _ => { }
+        }
+    }
+}
+
+/// The cache stored in a Discord context.
+#[derive(Debug, Default)]
+pub(crate) struct DiscordCache {
+    data: RwLock<CacheData>,
+}
+impl DiscordCache {
+    /// Clears the cache, and sets the intents that are used to decide what to cache.
+    pub(crate) fn reset(&self, intents: EnumSet<GatewayIntent>) {
+        *self.data.write() = CacheData { intents, ..Default::default() };
+    }
+
+    /// Updates the cache from a gateway event.
+    pub(crate) fn update(&self, event: &GatewayEvent) {
+        self.data.write().update(event);
+    }
+}
+
+impl DiscordContext {
+    /// Returns a guild from the cache, if it is present.
+    ///
+    /// The `roles` and `channels` fields of the returned guild are filled in from the cache. The
+    /// `members` field is always empty, and [`DiscordContext::cached_member`] should be used
+    /// instead. The `voice_states` field is kept up to date if the handler requests the
+    /// [`GatewayIntent::GuildVoiceStates`] intent.
+    pub fn cached_guild(&self, id: impl Into<GuildId>) -> Option<Guild> {
+        let data = self.data.cache.data.read();
+        let cached = data.guilds.get(&id.into())?;
+        let mut guild = cached.guild.clone();
+        guild.roles = cached.roles.iter().filter_map(|x| data.roles.get(x)).cloned().collect();
+        guild.channels = cached.channels.iter()
+            .filter_map(|x| data.channels.get(x))
+            .cloned()
+            .collect();
+        Some(guild)
+    }
+
+    /// Returns a channel from the cache, if it is present.
+    pub fn cached_channel(&self, id: impl Into<ChannelId>) -> Option<Channel> {
+        self.data.cache.data.read().channels.get(&id.into()).cloned()
+    }
+
+    /// Returns a role from the cache, if it is present.
+    pub fn cached_role(&self, id: impl Into<RoleId>) -> Option<Role> {
+        self.data.cache.data.read().roles.get(&id.into()).cloned()
+    }
+
+    /// Returns a guild member from the cache, if it is present.
+    pub fn cached_member(
+        &self, guild: impl Into<GuildId>, user: impl Into<UserId>,
+    ) -> Option<Member> {
+        let data = self.data.cache.data.read();
+        data.guilds.get(&guild.into())?.members.get(&user.into()).cloned()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn guild_create(id: u64, channel: u64, role: u64) -> GatewayEvent {
+        let guild = serde_json::from_value(json!({
+            "id": id.to_string(),
+            "name": "Test Guild",
+            "owner_id": "1",
+            "region": "us-west",
+            "afk_timeout": 300,
+            "verification_level": 0,
+            "default_message_notifications": 0,
+            "explicit_content_filter": 0,
+            "roles": [{
+                "id": role.to_string(),
+                "name": "@everyone",
+                "color": 0,
+                "hoist": false,
+                "position": 0,
+                "permissions": "0",
+                "managed": false,
+                "mentionable": false,
+            }],
+            "emojis": [],
+            "features": [],
+            "mfa_level": 0,
+            "system_channel_flags": 0,
+            "channels": [{ "id": channel.to_string(), "type": 0 }],
+        })).unwrap();
+        GatewayEvent::GuildCreate(GuildCreateEvent(guild))
+    }
+
+    fn ready(shard: ShardId) -> GatewayEvent {
+        GatewayEvent::Ready(serde_json::from_value(json!({
+            "v": 9,
+            "user": { "id": "1", "username": "Test", "discriminator": "0001", "avatar": null },
+            "private_channels": [],
+            "guilds": [],
+            "session_id": "session",
+            "shard": shard,
+        })).unwrap())
+    }
+
+    #[test]
+    fn caches_guild_contents() {
+        let mut cache = CacheData { intents: GatewayIntent::Guilds.into(), ..Default::default() };
+        cache.update(&guild_create(1 << 22, 10, 20));
+        let guild = &cache.guilds[&GuildId(Snowflake(1 << 22))];
+        assert!(guild.channels.contains(&ChannelId(Snowflake(10))));
+        assert!(guild.roles.contains(&RoleId(Snowflake(20))));
+        assert!(guild.guild.channels.is_empty() && guild.guild.roles.is_empty());
+        assert_eq!(cache.channels[&ChannelId(Snowflake(10))].guild_id, Some(guild.guild.id));
+        assert!(cache.roles.contains_key(&RoleId(Snowflake(20))));
+    }
+
+    #[test]
+    fn new_session_clears_shard_guilds() {
+        let mut cache = CacheData { intents: GatewayIntent::Guilds.into(), ..Default::default() };
+        // With two shards, the first guild is handled by shard 0 and the second by shard 1.
+        cache.update(&guild_create(2 << 22, 10, 20));
+        cache.update(&guild_create(3 << 22, 11, 21));
+
+        cache.update(&ready(ShardId(1, 2)));
+        assert!(cache.guilds.contains_key(&GuildId(Snowflake(2 << 22))));
+        assert!(cache.channels.contains_key(&ChannelId(Snowflake(10))));
+        assert!(cache.roles.contains_key(&RoleId(Snowflake(20))));
+        assert!(!cache.guilds.contains_key(&GuildId(Snowflake(3 << 22))));
+        assert!(!cache.channels.contains_key(&ChannelId(Snowflake(11))));
+        assert!(!cache.roles.contains_key(&RoleId(Snowflake(21))));
+    }
+}
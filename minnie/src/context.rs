@@ -1,16 +1,20 @@
 //!
 
 use crate::gateway::{GatewayController, GatewayConfig};
-use crate::http::{HttpConfig, RateLimits};
+use crate::http::{HttpConfig, RateLimits, DEFAULT_API_BASE_URL};
 use derive_setters::*;
+use fxhash::FxHashMap;
 use minnie_errors::*;
 use minnie_model::gateway::PresenceUpdate;
-use minnie_model::types::{DiscordClientSecret, DiscordToken, Snowflake};
-use reqwest::{Client, ClientBuilder};
+use minnie_model::types::{ChannelId, DiscordClientSecret, DiscordToken, Snowflake, UserId};
+use minnie_model::user::{FullUser, User};
+use parking_lot::RwLock;
+use reqwest::{Client, ClientBuilder, Proxy};
 use reqwest::header::*;
 use serde::*;
 use std::borrow::Cow;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio_rustls::TlsConnector;
 use tokio_rustls::rustls::ClientConfig;
 
@@ -27,8 +31,15 @@ pub(crate) struct DiscordContextData {
 
     pub library_name: Cow<'static, str>,
     pub http_user_agent: Cow<'static, str>,
+    #[derivative(Debug="ignore")]
+    pub http_user_agent_header: HeaderValue,
+    pub api_base_url: Cow<'static, str>,
     pub client_token: DiscordToken,
     pub client_secret: Option<DiscordClientSecret>,
+    pub current_user: RwLock<Option<FullUser>>,
+    pub dm_channels: RwLock<FxHashMap<UserId, ChannelId>>,
+    #[cfg(feature = "cache")]
+    pub cache: crate::cache::DiscordCache,
 
     pub http_client: Client,
     pub rate_limits: crate::http::RateLimits,
@@ -45,8 +56,9 @@ impl Drop for DiscordContextData {
     }
 }
 
-const DEFAULT_USER_AGENT: &str =
-    concat!("DiscordBot (https://github.com/Lymia/minnie, ", env!("CARGO_PKG_VERSION"), ")");
+const DEFAULT_USER_AGENT: &str = concat!(
+    "DiscordBot (", env!("CARGO_PKG_REPOSITORY"), ", ", env!("CARGO_PKG_VERSION"), ")",
+);
 
 /// Handles all features relating to a particular Discord bot.
 ///
@@ -63,6 +75,21 @@ impl DiscordContext {
     }
 
     /// Returns a builder that allows configuring the Discord context's settings.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::types::DiscordToken;
+    /// # use std::time::Duration;
+    /// fn make_context(token: DiscordToken) -> Result<DiscordContext> {
+    ///     DiscordContext::builder(token)
+    ///         .library_name("my_bot_framework")
+    ///         .request_timeout(Duration::from_secs(30))
+    ///         .build()
+    /// }
+    /// ```
     pub fn builder(client_token: DiscordToken) -> DiscordContextBuilder {
         DiscordContextBuilder::new(client_token)
     }
@@ -77,15 +104,59 @@ impl DiscordContext {
         self.data.context_id
     }
 
+    /// Returns the base URL used for API calls.
+    pub fn api_base_url(&self) -> &str {
+        &self.data.api_base_url
+    }
+
     /// Returns an unique ID for this context. Unlike [`DiscordContext::id`], this should be
     /// to be entirely unique in normal usage, as it cannot be manually set.
     pub fn unique_id(&self) -> DiscordContextId {
         self.data.unique_context_id
     }
+
+    /// Returns the bot's user, if it is known.
+    ///
+    /// This is set when a shard receives a `Ready` event or when
+    /// [`DiscordContext::current_user`] is called, and is kept up to date by `User Update`
+    /// events while the gateway is connected.
+    pub fn cached_current_user(&self) -> Option<FullUser> {
+        self.data.current_user.read().clone()
+    }
+
+    /// Returns the bot's user ID, if it is known.
+    ///
+    /// See [`DiscordContext::cached_current_user`] for details.
+    pub fn current_user_id(&self) -> Option<UserId> {
+        self.data.current_user.read().as_ref().map(|x| x.user.id)
+    }
+
+    /// Returns the bot's user, retrieving it from the Discord API if it is not already cached.
+    pub async fn current_user(&self) -> Result<FullUser> {
+        if let Some(user) = self.cached_current_user() {
+            return Ok(user)
+        }
+        let user = self.raw().get_current_user().await?;
+        self.set_current_user(user.clone());
+        Ok(user)
+    }
+
+    pub(crate) fn set_current_user(&self, user: FullUser) {
+        *self.data.current_user.write() = Some(user);
+    }
+    pub(crate) fn update_current_user(&self, user: &User) {
+        let mut lock = self.data.current_user.write();
+        if let Some(current) = &mut *lock {
+            if current.user.id == user.id {
+                current.user = user.clone();
+            }
+        }
+    }
 }
 
 /// A builder for a [`DiscordContext`].
-#[derive(Debug, Setters)]
+#[derive(Derivative, Setters)]
+#[derivative(Debug)]
 #[setters(strip_option)]
 pub struct DiscordContextBuilder {
     /// Sets the client token for this builder.
@@ -97,9 +168,24 @@ pub struct DiscordContextBuilder {
     /// bot.
     context_id: Option<DiscordContextId>,
     /// Sets the library name reported to the Discord API.
+    #[setters(into)]
     library_name: Option<String>,
     /// Sets the user agent used in HTTP requests made by the bot.
+    ///
+    /// Discord requires user agents of the form `DiscordBot ($url, $version)`, where the URL and
+    /// version identify the library or bot making the requests. Additional information may be
+    /// appended after this. Defaults to
+    /// `DiscordBot (https://github.com/SylphieBot/minnie, $version)`.
+    ///
+    /// The user agent is also used when connecting to the gateway.
+    #[setters(into)]
     http_user_agent: Option<String>,
+    /// Sets the base URL used for API calls, e.g. `https://discord.com/api/v9`.
+    ///
+    /// This is mainly useful for testing against a mock server. Note that the data models in
+    /// this library target the default API version, and other versions may not be compatible.
+    #[setters(into)]
+    api_base_url: Option<String>,
     /// Sets the presence sent to the Discord gateway.
     default_presence: PresenceUpdate,
     /// Sets the configuration of the gateway.
@@ -108,6 +194,58 @@ pub struct DiscordContextBuilder {
     http_config: HttpConfig,
     /// Sets the client secret used for OAuth2 operations.
     client_secret: Option<DiscordClientSecret>,
+    /// Sets the HTTP client used to make API calls.
+    ///
+    /// The user agent and authorization headers are added to each request, and do not need to
+    /// be configured on the client. Together with
+    /// [`api_base_url`](`DiscordContextBuilder::api_base_url`), this allows API calls to be
+    /// made against a local mock server:
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::model::types::{ChannelId, DiscordToken};
+    /// use hyper::{Body, Response, Server};
+    /// use hyper::service::{make_service_fn, service_fn};
+    ///
+    /// # #[tokio::main(basic_scheduler)]
+    /// # async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let make_service = make_service_fn(|_| async {
+    ///     Ok::<_, hyper::Error>(service_fn(|request| async move {
+    ///         assert_eq!(request.uri().path(), "/api/channels/1234");
+    ///         let channel = r#"{"id": "1234", "type": 0, "name": "general"}"#;
+    ///         Ok::<_, hyper::Error>(Response::new(Body::from(channel)))
+    ///     }))
+    /// });
+    /// let server = Server::bind(&([127, 0, 0, 1], 0).into()).serve(make_service);
+    /// let address = server.local_addr();
+    /// tokio::spawn(server);
+    ///
+    /// let ctx = DiscordContext::builder(DiscordToken::new("MTIzNA.abc.def")?)
+    ///     .http_client(reqwest::Client::new())
+    ///     .api_base_url(format!("http://{}/api", address))
+    ///     .build()?;
+    /// let channel = ctx.channel(ChannelId::from(1234)).get().await?;
+    /// assert_eq!(channel.id, ChannelId::from(1234));
+    /// assert_eq!(channel.name.as_deref(), Some("general"));
+    /// # Ok(())
+    /// # }
+    /// ```
+    #[derivative(Debug="ignore")]
+    http_client: Option<Client>,
+    /// Sets the timeout for connecting to Discord's API.
+    ///
+    /// This is ignored if a custom [`http_client`](`DiscordContextBuilder::http_client`) is set.
+    connect_timeout: Option<Duration>,
+    /// Sets the timeout for an individual HTTP request, from connecting until the response body
+    /// has been received.
+    ///
+    /// This is ignored if a custom [`http_client`](`DiscordContextBuilder::http_client`) is set.
+    request_timeout: Option<Duration>,
+    /// Sets the URL of a proxy to make HTTP requests through.
+    ///
+    /// This is ignored if a custom [`http_client`](`DiscordContextBuilder::http_client`) is set.
+    #[setters(into)]
+    proxy: Option<String>,
 }
 impl DiscordContextBuilder {
     fn new(client_token: DiscordToken) -> Self {
@@ -115,14 +253,23 @@ impl DiscordContextBuilder {
             context_id: None,
             library_name: None,
             http_user_agent: None,
+            api_base_url: None,
             client_token,
             client_secret: None,
+            http_client: None,
+            connect_timeout: None,
+            request_timeout: None,
+            proxy: None,
             default_presence: PresenceUpdate::default(),
             gateway_config: GatewayConfig::default(),
             http_config: HttpConfig::default(),
         }
     }
 
+    /// Creates the Discord context.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the API base URL, user agent or proxy URL are
+    /// invalid.
     pub fn build(self) -> Result<DiscordContext> {
         let context_id = match self.context_id {
             Some(id) => id,
@@ -136,17 +283,40 @@ impl DiscordContextBuilder {
             Some(ua) => ua.into(),
             None => DEFAULT_USER_AGENT.into(),
         };
-        let mut headers = HeaderMap::new();
-        headers.insert(USER_AGENT, HeaderValue::from_str(&http_user_agent)
-            .invalid_input("User agent contains non-ASCII characters.")?);
-        headers.insert(HeaderName::from_static("authorization"),
-                       self.client_token.to_header_value());
-        let http_client = ClientBuilder::new()
-            .use_rustls_tls()
-            .default_headers(headers)
-            .referer(false)
-            .build()
-            .internal_err("Failed to create HTTP client.")?;
+        let api_base_url: Cow<str> = match self.api_base_url {
+            Some(url) => {
+                url::Url::parse(&url).invalid_input("Could not parse API base URL.")?;
+                url.trim_end_matches('/').to_string().into()
+            }
+            None => DEFAULT_API_BASE_URL.into(),
+        };
+        let http_user_agent_header = HeaderValue::from_str(&http_user_agent)
+            .invalid_input("User agent contains non-ASCII characters.")?;
+        let http_client = match self.http_client {
+            Some(client) => client,
+            None => {
+                let mut headers = HeaderMap::new();
+                headers.insert(USER_AGENT, http_user_agent_header.clone());
+                headers.insert(HeaderName::from_static("authorization"),
+                               self.client_token.to_header_value());
+                let mut builder = ClientBuilder::new()
+                    .use_rustls_tls()
+                    .default_headers(headers)
+                    .referer(false);
+                if let Some(timeout) = self.connect_timeout {
+                    builder = builder.connect_timeout(timeout);
+                }
+                if let Some(timeout) = self.request_timeout {
+                    builder = builder.timeout(timeout);
+                }
+                if let Some(proxy) = &self.proxy {
+                    builder = builder.proxy(
+                        Proxy::all(proxy.as_str()).invalid_input("Could not parse proxy URL.")?
+                    );
+                }
+                builder.build().internal_err("Failed to create HTTP client.")?
+            }
+        };
 
         let mut rustls_config = ClientConfig::new();
         rustls_config.root_store.add_server_trust_anchors(&webpki_roots::TLS_SERVER_ROOTS);
@@ -154,9 +324,13 @@ impl DiscordContextBuilder {
         let data = Arc::new(DiscordContextData {
             context_id,
             unique_context_id: DiscordContextId(Snowflake::random()),
-            library_name, http_user_agent,
+            library_name, http_user_agent, http_user_agent_header, api_base_url,
             client_token: self.client_token,
             client_secret: self.client_secret,
+            current_user: RwLock::new(None),
+            dm_channels: Default::default(),
+            #[cfg(feature = "cache")]
+            cache: Default::default(),
             http_client,
             rate_limits: RateLimits::new(self.http_config),
             rustls_connector: TlsConnector::from(Arc::new(rustls_config)),
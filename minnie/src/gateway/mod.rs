@@ -3,25 +3,32 @@
 use crate::context::DiscordContext;
 use derive_setters::*;
 use enumset::EnumSet;
+use futures::channel::mpsc;
+use futures::stream::{Stream, StreamExt};
 use fxhash::FxHashMap;
 use minnie_errors::*;
 use minnie_model::event::*;
 use minnie_model::gateway::*;
+use minnie_model::guild::Member;
 use minnie_model::types::*;
+use minnie_model::user::{Activity, UserStatus};
 use parking_lot::{Mutex, RwLock};
 use rand::Rng;
+use std::borrow::Cow;
 use std::error::{Error as StdError};
 use std::fmt::Write;
+use std::future::Future;
+use std::pin::Pin;
 use std::sync::Arc;
+use std::task::{Context, Poll};
 use std::time::Duration;
 use tokio::time;
 use tokio::runtime::Handle;
 use tokio_tungstenite::tungstenite::protocol::CloseFrame;
 
 mod shard;
-pub use minnie_model::gateway::{GuildMembersRequest, PresenceUpdate};
+pub use minnie_model::gateway::{ConnectionProperties, GuildMembersRequest, PresenceUpdate};
 
-// TODO: Implement rate limits.
 // TODO: Is there a way we can avoid the timeout check in ws.rs?
 // TODO: Add a way to get gateway status.
 // TODO: Add tests.
@@ -45,10 +52,27 @@ pub enum GatewayError<T: GatewayHandler> {
     ///
     /// This error cannot be ignored.
     HeartbeatTimeout,
+    /// The gateway did not send any packets, including heartbeat acknowledgements, within the
+    /// [`idle_timeout`](`GatewayConfig::idle_timeout`), even though the connection is still open.
+    ///
+    /// This error cannot be ignored.
+    IdleTimeout,
     /// The remote host cleanly closed the Websocket connection.
     ///
     /// This error cannot be ignored.
     RemoteHostDisconnected(Option<CloseFrame<'static>>),
+    /// The gateway closed the connection with code 4011, indicating that the bot is in too many
+    /// guilds to connect without sharding, or with the configured number of shards.
+    ///
+    /// This error cannot be ignored, and is treated as a [`GatewayResponse::Shutdown`] by
+    /// default, as reconnecting with the same shard count will fail again.
+    ShardingRequired(CloseFrame<'static>),
+    /// The gateway closed the connection with code 4010, indicating that an invalid shard ID or
+    /// shard count was sent while identifying.
+    ///
+    /// This error cannot be ignored, and is treated as a [`GatewayResponse::Shutdown`] by
+    /// default.
+    InvalidShard(CloseFrame<'static>),
     /// The error occurred while connecting to the gateway.
     ///
     /// This error cannot be ignored.
@@ -70,7 +94,10 @@ pub enum GatewayError<T: GatewayHandler> {
     /// The event handler panicked.
     EventHandlingPanicked(Error),
     /// An unknown opcode was encountered.
-    UnknownOpcode(i128),
+    ///
+    /// If [`GatewayConfig::keep_unknown_payloads`] is set, this contains the raw JSON of the
+    /// packet's `d` field.
+    UnknownOpcode(i128, Option<String>),
     /// An unknown event was encountered.
     UnknownEvent(String),
     /// The gateway panicked. This error forces a complete shutdown of the gateway.
@@ -84,8 +111,16 @@ impl <T: GatewayHandler> GatewayError<T> {
                 format!("Shard #{} disconnected: Did not receieve Hello", shard),
             GatewayError::HeartbeatTimeout =>
                 format!("Shard #{} disconnected: Did not receive Heartbeat ACK", shard),
+            GatewayError::IdleTimeout =>
+                format!("Shard #{} disconnected: Did not receive any packets", shard),
             GatewayError::RemoteHostDisconnected(data) =>
                 format!("Shard #{} disconnected: {:?}", shard, data),
+            GatewayError::ShardingRequired(frame) =>
+                format!("Shard #{} disconnected: more shards are required (close code {}: {})",
+                        shard, u16::from(frame.code), frame.reason),
+            GatewayError::InvalidShard(frame) =>
+                format!("Shard #{} disconnected: invalid shard configuration (close code {}: {})",
+                        shard, u16::from(frame.code), frame.reason),
             GatewayError::ConnectionError(_) =>
                 format!("Shard #{} failed to connect", shard),
             GatewayError::AuthenticationFailure =>
@@ -97,8 +132,10 @@ impl <T: GatewayHandler> GatewayError<T> {
                 format!("Shard #{} could not send message", shard),
             GatewayError::UnexpectedPacket(_) =>
                 format!("Shard #{} received an unexpected packet", shard),
-            GatewayError::UnknownOpcode(op) =>
+            GatewayError::UnknownOpcode(op, None) =>
                 format!("Shard #{} received an unknown packet: {}", shard, op),
+            GatewayError::UnknownOpcode(op, Some(data)) =>
+                format!("Shard #{} received an unknown packet: {} ({})", shard, op, data),
             GatewayError::UnknownEvent(name) =>
                 format!("Shard #{} received an unknown event: {}", shard, name),
             GatewayError::EventHandlingFailed(_) =>
@@ -191,6 +228,33 @@ pub trait GatewayHandler: Sized + Send + Sync + 'static {
         Ok(())
     }
 
+    /// Handles a `Guild Create` event for a guild that was previously unavailable.
+    ///
+    /// This occurs for every guild the bot is in after a new session is created, and when a
+    /// guild becomes available again after an outage. A `Guild Create` event caused by the bot
+    /// joining a new guild is passed to [`GatewayHandler::on_event`] instead.
+    ///
+    /// By default, this passes the event to [`GatewayHandler::on_event`].
+    fn on_guild_available(
+        &self, ctx: &GatewayContext, ev: GuildCreateEvent,
+    ) -> StdResult<(), Self::Error> {
+        self.on_event(ctx, GatewayEvent::GuildCreate(ev))
+    }
+
+    /// Called when a shard has connected to the gateway.
+    ///
+    /// `resumed` is `true` if the connection was established by resuming an existing session,
+    /// in which case this is called when the `Resumed` event is received. Otherwise, a new
+    /// session was created, and this is called when the `Ready` event is received. State that
+    /// depends on the session, such as cached guilds, only needs to be reset in the latter case.
+    fn on_connect(&self, _: &GatewayContext, _resumed: bool) { }
+
+    /// Called when a shard that was connected to the gateway disconnects.
+    ///
+    /// `resumed` is `true` if the connection was established by resuming an existing session,
+    /// rather than by identifying with a new one.
+    fn on_disconnect(&self, _: &GatewayContext, _resumed: bool) { }
+
     /// Called when an error occurs in the gateway. This method should create an error report of
     /// some kind and then return.
     #[inline(never)]
@@ -218,7 +282,7 @@ pub trait GatewayHandler: Sized + Send + Sync + 'static {
     /// Decides how the gateway should respond to a particular error.
     ///
     /// By default, this ignores errors originating in [`GatewayHandler`], unknown packets, and
-    /// unknown events.
+    /// unknown events, and shuts down the gateway if the shard configuration is rejected.
     #[inline(never)]
     fn on_error(
         &self, _: &GatewayContext, err: &GatewayError<Self>,
@@ -227,8 +291,10 @@ pub trait GatewayHandler: Sized + Send + Sync + 'static {
             GatewayError::UnexpectedPacket(_) => GatewayResponse::Ignore,
             GatewayError::EventHandlingFailed(_) => GatewayResponse::Ignore,
             GatewayError::EventHandlingPanicked(_) => GatewayResponse::Ignore,
-            GatewayError::UnknownOpcode(_) => GatewayResponse::Ignore,
+            GatewayError::UnknownOpcode(..) => GatewayResponse::Ignore,
             GatewayError::UnknownEvent(_) => GatewayResponse::Ignore,
+            GatewayError::ShardingRequired(_) => GatewayResponse::Shutdown,
+            GatewayError::InvalidShard(_) => GatewayResponse::Shutdown,
             _ => GatewayResponse::Reconnect,
         }
     }
@@ -268,6 +334,23 @@ pub trait GatewayHandler: Sized + Send + Sync + 'static {
     }
 }
 
+/// Logs a warning for each event a handler does not ignore, but will never receive due to the
+/// intents it requests.
+fn check_intents(ctx: &GatewayContext, dispatch: &impl GatewayHandler) {
+    let intents = dispatch.intents();
+    for event in GatewayEventType::known_events() {
+        if let Some(required) = event.intent() {
+            if (required & intents).is_empty() && !dispatch.ignores_event(ctx, &event) {
+                warn!(
+                    "Event {} is not ignored by the gateway handler, but will never be received \
+                     without one of the intents {:?}.",
+                    event, required,
+                );
+            }
+        }
+    }
+}
+
 /// The type of compression that shards are expected to use.
 #[derive(Copy, Clone, Eq, PartialEq, Debug, Hash)]
 pub enum CompressionType {
@@ -294,10 +377,28 @@ pub enum ShardFilter {
 }
 impl ShardFilter {
     /// Whether this filter will accept a shard with the given ID.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::gateway::ShardFilter;
+    /// let filter = ShardFilter::Range(2, 5);
+    /// assert!(filter.accepts_shard(2));
+    /// assert!(filter.accepts_shard(3));
+    /// assert!(filter.accepts_shard(5));
+    /// assert!(!filter.accepts_shard(0));
+    /// assert!(!filter.accepts_shard(1));
+    /// assert!(!filter.accepts_shard(6));
+    ///
+    /// let single = ShardFilter::Range(4, 4);
+    /// assert!(single.accepts_shard(4));
+    /// assert!(!single.accepts_shard(3));
+    /// assert!(!single.accepts_shard(5));
+    /// ```
     pub fn accepts_shard(&self, id: u32) -> bool {
         match self {
             ShardFilter::NoFilter => true,
-            ShardFilter::Range(min, max) => *min >= id && id <= *max,
+            ShardFilter::Range(min, max) => id >= *min && id <= *max,
             ShardFilter::Custom(f) => f(id),
         }
     }
@@ -326,6 +427,14 @@ pub struct GatewayConfig {
     ///
     /// Changes to this field are only applied on shard restart.
     pub guild_subscription: bool,
+    /// The connection properties sent in the identify packet.
+    ///
+    /// If this is `None`, the operating system and the library name configured in the
+    /// [`DiscordContext`] are sent.
+    ///
+    /// Changes to this field are only applied on shard restart.
+    #[setters(strip_option)]
+    pub connection_properties: Option<ConnectionProperties>,
 
     /// How long the shard manager will wait before reconnecting a shard.
     pub backoff_initial: Duration,
@@ -335,6 +444,47 @@ pub struct GatewayConfig {
     pub backoff_cap: Duration,
     /// The maximum amount of time to randomly add between connection attempts.
     pub backoff_variation: Option<Duration>,
+
+    /// The maximum amount of time to wait for
+    /// [`fetch_all_members`](`GatewayController::fetch_all_members`) to complete.
+    pub member_request_timeout: Duration,
+    /// The maximum number of commands, such as guild members requests, that may be waiting to
+    /// be sent on each shard.
+    ///
+    /// Commands are queued while a shard is disconnected or has exhausted Discord's rate limit
+    /// for gateway commands. Once the queue is full, further commands fail with
+    /// [`ErrorKind::GatewayQueueFull`] until queued commands have been sent.
+    ///
+    /// A capacity of zero is treated as a capacity of one.
+    ///
+    /// Changes to this field are only applied on gateway restart.
+    pub command_queue_capacity: usize,
+
+    /// How long to wait for a heartbeat to be acknowledged after the next heartbeat is due,
+    /// before treating the connection as timed out.
+    ///
+    /// By default, this is zero, and a connection times out if a heartbeat has not been
+    /// acknowledged by the time the next one is due. Increasing this may reduce spurious
+    /// reconnects on connections with high latency variance.
+    ///
+    /// If [`idle_timeout`](`GatewayConfig::idle_timeout`) is set, it also limits how long a
+    /// connection may wait for a heartbeat acknowledgement, regardless of this grace period.
+    pub heartbeat_timeout_grace: Duration,
+    /// How long a connection may go without receiving any packet, including heartbeat
+    /// acknowledgements, before it is treated as timed out.
+    ///
+    /// This catches connections that remain open but have stopped delivering packets between
+    /// heartbeat checks. If `None`, twice the heartbeat interval sent by Discord plus the
+    /// [`heartbeat_timeout_grace`](`GatewayConfig::heartbeat_timeout_grace`) is used, so that
+    /// the grace period is never cut short. Setting this to a shorter duration than that caps
+    /// the grace period.
+    pub idle_timeout: Option<Duration>,
+
+    /// Whether to retain the contents of packets with unknown opcodes.
+    ///
+    /// This is intended for debugging, and allows the contents of such packets to be inspected
+    /// via [`GatewayError::UnknownOpcode`].
+    pub keep_unknown_payloads: bool,
 }
 impl GatewayConfig {
     /// Creates a new configuration with the default settings.
@@ -349,16 +499,23 @@ impl Default for GatewayConfig {
             shard_filter: ShardFilter::NoFilter,
             compress: CompressionType::TransportCompression,
             guild_subscription: true,
+            connection_properties: None,
             backoff_initial: Duration::from_secs(1),
             backoff_factor: 2.0,
             backoff_cap: Duration::from_secs(60),
             backoff_variation: Some(Duration::from_secs(1)),
+            member_request_timeout: Duration::from_secs(120),
+            command_queue_capacity: 256,
+            heartbeat_timeout_grace: Duration::from_secs(0),
+            idle_timeout: None,
+            keep_unknown_payloads: false,
         }
     }
 }
 
 struct CurrentGateway {
     shared: Arc<shard::GatewayState>,
+    shard_count: u32,
     shards: Vec<Arc<shard::ShardState>>,
     shard_id_map: FxHashMap<ShardId, usize>,
 }
@@ -397,6 +554,9 @@ impl GatewayController {
 
     /// Connects the bot to the Discord gateway. If the bot is already connected, it disconnects
     /// the previous connection.
+    ///
+    /// In debug builds, this logs a warning for any event the handler does not ignore, but that
+    /// requires an intent the handler does not request.
     pub async fn connect(
         &self, executor: &Handle, dispatch: impl GatewayHandler,
     ) -> Result<()> {
@@ -410,7 +570,10 @@ impl GatewayController {
             None => endpoint.shards,
         };
 
-        let gateway = Arc::new(shard::GatewayState::new(&endpoint.url, self.shared.clone()));
+        let max_concurrency = endpoint.session_start_limit.max_concurrency;
+        let gateway = Arc::new(
+            shard::GatewayState::new(&endpoint.url, max_concurrency, self.shared.clone()),
+        );
 
         let mut shards = Vec::new();
         let mut shard_id_map = FxHashMap::default();
@@ -418,11 +581,12 @@ impl GatewayController {
             if config.shard_filter.accepts_shard(id) {
                 let id = ShardId(id, shard_count);
                 shard_id_map.insert(id, shards.len());
-                shards.push(Arc::new(shard::ShardState::new(id, gateway.clone())));
+                let capacity = config.command_queue_capacity;
+                shards.push(Arc::new(shard::ShardState::new(id, gateway.clone(), capacity)));
             }
         }
         let gateway_state = Arc::new(CurrentGateway {
-            shards, shard_id_map,
+            shards, shard_id_map, shard_count,
             shared: gateway.clone(),
         });
 
@@ -430,10 +594,22 @@ impl GatewayController {
         let mut state = self.current.lock();
         if let Some(old_gateway) = state.take() {
             old_gateway.shared.shutdown();
+            self.shared.clear_member_requests();
         }
         *state = Some(gateway_state.clone());
         drop(state);
 
+        // Check that the handler will actually receive the events it wants.
+        if cfg!(debug_assertions) {
+            if let Some(shard) = gateway_state.shards.first() {
+                let gateway_ctx = GatewayContext { ctx: ctx.clone(), shard_id: shard.id };
+                check_intents(&gateway_ctx, &dispatch);
+            }
+        }
+
+        #[cfg(feature = "cache")]
+        ctx.data.cache.reset(dispatch.intents());
+
         // Start each shard in the gateway.
         let dispatch = Arc::new(dispatch);
         for shard in &gateway_state.shards {
@@ -450,6 +626,7 @@ impl GatewayController {
         };
         if let Some(gateway) = &gateway {
             gateway.shared.shutdown();
+            self.shared.clear_member_requests();
         }
         gateway
     }
@@ -466,6 +643,29 @@ impl GatewayController {
         }
     }
 
+    /// Disconnects the bot from the Discord gateway, allowing shards to send any queued presence
+    /// updates or guild member requests first, then waits for all shards to disconnect.
+    ///
+    /// Shards with queued commands that are still connecting or resuming finish connecting
+    /// before sending them. Shards with nothing queued disconnect immediately.
+    ///
+    /// If the shards have not disconnected after the given timeout, they are forcefully
+    /// disconnected as with [`GatewayController::disconnect_wait`].
+    pub async fn disconnect_graceful(&self, timeout: Duration) {
+        let gateway = {
+            let mut state = self.current.lock();
+            state.take()
+        };
+        if let Some(gateway) = gateway {
+            gateway.shared.drain();
+            if time::timeout(timeout, gateway.wait_shutdown()).await.is_err() {
+                gateway.shared.shutdown();
+                gateway.wait_shutdown().await;
+            }
+            self.shared.clear_member_requests();
+        }
+    }
+
     /// Restarts all shards of the gateway. Does nothing if the gateway is not connected.
     pub fn reconnect_shards(&self) {
         self.reconnect_shards_partial(|_| true);
@@ -484,6 +684,27 @@ impl GatewayController {
         }
     }
 
+    /// Computes the intents needed to receive the given types of events.
+    ///
+    /// This can be used to implement [`GatewayHandler::intents`] for handlers that only care
+    /// about a few types of events. A warning is logged if any of the intents returned are
+    /// privileged, as these must be enabled for the bot in the developer portal.
+    pub fn intents_for_events<'a>(
+        events: impl IntoIterator<Item = &'a GatewayEventType>,
+    ) -> EnumSet<GatewayIntent> {
+        let mut intents = EnumSet::new();
+        for event in events {
+            if let Some(intent) = event.intent() {
+                intents |= intent;
+            }
+        }
+        let privileged = intents & GatewayIntent::privileged();
+        if !privileged.is_empty() {
+            warn!("Handled events require privileged intents: {:?}", privileged);
+        }
+        intents
+    }
+
     /// Returns the current presence for the bot.
     pub fn presence(&self) -> PresenceUpdate {
         self.shared.presence.read().clone()
@@ -503,6 +724,20 @@ impl GatewayController {
         }
     }
 
+    /// Sets the bot's presence to online with a single activity.
+    ///
+    /// See [`GatewayController::set_presence`] for details.
+    pub fn set_activity(&self, activity: Activity) {
+        self.set_presence(PresenceUpdate::from(UserStatus::Online).activities(vec![activity]));
+    }
+
+    /// Sets the bot's presence to online and "Playing {name}".
+    ///
+    /// See [`GatewayController::set_presence`] for details.
+    pub fn set_playing(&self, name: impl Into<Cow<'static, str>>) {
+        self.set_activity(Activity::playing(name));
+    }
+
     /// Returns the current configuration for the gateway.
     pub fn config(&self) -> GatewayConfig {
         self.shared.config.read().clone()
@@ -516,24 +751,249 @@ impl GatewayController {
         *self.shared.config.write() = config;
     }
 
+    /// Returns the total number of shards the gateway is connected with, or `None` if the
+    /// gateway is not connected.
+    ///
+    /// This may differ from [`GatewayConfig::shard_count`], as the shard count recommended by
+    /// Discord is used when none is configured.
+    pub fn shard_count(&self) -> Option<u32> {
+        self.current.lock().as_ref().map(|x| x.shard_count)
+    }
+
+    /// Returns the shard that receives events for a given guild, or `None` if the gateway is not
+    /// connected.
+    ///
+    /// The returned shard may not be handled by this gateway if a
+    /// [`shard_filter`](`GatewayConfig::shard_filter`) is set.
+    pub fn shard_id_for_guild(&self, guild: GuildId) -> Option<ShardId> {
+        self.shard_count().map(|count| guild.shard_for_guild(count))
+    }
+
     /// Sends a guild members request on the given shard. If no shard is given, one is chosen at
     /// random.
     ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the gateway is not connected, or with
+    /// [`ErrorKind::GatewayQueueFull`] if the shard's command queue is full. See
+    /// [`GatewayConfig::command_queue_capacity`] for details.
+    ///
     /// # Panics
     ///
     /// If the given ShardId is not contained within the gateway.
     pub fn request_guild_members(
         &self, shard: Option<ShardId>, packet: GuildMembersRequest,
-    ) {
+    ) -> Result<()> {
         let state = self.current.lock();
-        if let Some(state) = &*state {
-            let shard = match shard {
-                Some(id) => {
-                    *state.shard_id_map.get(&id).expect("Shard not found in gateway.")
-                }
-                None => rand::thread_rng().gen_range(0, state.shards.len()),
+        let state = match &*state {
+            Some(state) => state,
+            None => bail!(InvalidInput, "The gateway is not connected."),
+        };
+        let shard = match shard {
+            Some(id) => *state.shard_id_map.get(&id).expect("Shard not found in gateway."),
+            None => rand::thread_rng().gen_range(0, state.shards.len()),
+        };
+        state.shards[shard].request_guild_members(packet)
+    }
+
+    /// Sends an arbitrary packet on the given shard.
+    ///
+    /// This is intended for prototyping support for gateway features that this library does not
+    /// model yet, and is only available when the `unstable` feature is enabled. The packet is
+    /// sent as-is, with no checks on its contents.
+    ///
+    /// Packets with opcodes that are only received from Discord or that are used to manage the
+    /// connection itself (see [`GatewayOpcode::should_send`]) are rejected with
+    /// [`ErrorKind::InvalidInput`], as is sending on a shard not connected to by this gateway.
+    #[cfg(feature = "unstable")]
+    pub fn send_raw_packet(
+        &self, shard: ShardId, op: GatewayOpcode, data: serde_json::Value,
+    ) -> Result<()> {
+        ensure!(op.should_send(), InvalidInput, "Packets with this opcode cannot be sent.");
+        let state = self.current.lock();
+        let state = match &*state {
+            Some(state) => state,
+            None => bail!(InvalidInput, "The gateway is not connected."),
+        };
+        match state.shard_id_map.get(&shard) {
+            Some(shard) => state.shards[*shard].send_raw_packet(op, data),
+            None => bail!(InvalidInput, "The shard is not connected to by this gateway."),
+        }
+    }
+
+    /// Retrieves every member of a guild through the gateway.
+    ///
+    /// This sends a guild members request on the shard the guild belongs to, and waits until
+    /// every resulting `Guild Members Chunk` event has been received. These events are still
+    /// passed to the [`GatewayHandler`].
+    ///
+    /// This requires the [`GatewayIntent::GuildMembers`] intent, and fails if the gateway is not
+    /// connected, or the shard for the guild is not connected to by this gateway.
+    pub async fn fetch_all_members(&self, guild: GuildId) -> Result<Vec<Member>> {
+        let nonce = format!("{:016x}", rand::random::<u64>());
+        let recv = {
+            let state = self.current.lock();
+            let state = match &*state {
+                Some(state) => state,
+                None => bail!(InvalidInput, "The gateway is not connected."),
+            };
+            let shard_id = guild.shard_for_guild(state.shard_count);
+            let shard = match state.shard_id_map.get(&shard_id) {
+                Some(shard) => *shard,
+                None => bail!(InvalidInput, "The shard for this guild is not connected."),
             };
-            state.shards[shard].request_guild_members(packet);
+            let recv = self.shared.add_member_request(nonce.clone());
+            let packet = GuildMembersRequest::new(guild).nonce(nonce.clone());
+            if let Err(e) = state.shards[shard].request_guild_members(packet) {
+                self.shared.cancel_member_request(&nonce);
+                return Err(e);
+            }
+            recv
+        };
+        let timeout = self.shared.config.read().member_request_timeout;
+        match time::timeout(timeout, recv).await {
+            Ok(Ok(members)) => Ok(members),
+            Ok(Err(_)) => bail!(IoError, "Gateway disconnected before all members were received."),
+            Err(_) => {
+                self.shared.cancel_member_request(&nonce);
+                bail!(IoError, "Timed out waiting for guild members.")
+            }
         }
     }
+
+    /// Waits for the next gateway event matching a predicate.
+    ///
+    /// The predicate is checked against each event before it is passed to the
+    /// [`GatewayHandler`], and the matching event is still passed to the handler as usual. Only
+    /// events not ignored by [`GatewayHandler::ignores_event`] are checked. As the predicate is
+    /// called from the gateway's event loop, it should return quickly.
+    ///
+    /// This returns an error if no matching event is received before the timeout expires.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie::DiscordContext;
+    /// # use minnie::Result;
+    /// # use minnie::model::event::GatewayEvent;
+    /// # use minnie::model::types::MessageId;
+    /// # use std::time::Duration;
+    /// async fn wait_for_reaction(ctx: DiscordContext, message: MessageId) -> Result<()> {
+    ///     ctx.gateway().wait_for(move |ev| match ev {
+    ///         GatewayEvent::MessageReactionAdd(ev) => ev.message_id == message,
+    ///         _ => false,
+    ///     }, Duration::from_secs(60)).await?;
+    ///     Ok(())
+    /// }
+    /// ```
+    pub async fn wait_for(
+        &self,
+        predicate: impl Fn(&GatewayEvent) -> bool + Send + Sync + 'static,
+        timeout: Duration,
+    ) -> Result<GatewayEvent> {
+        let (id, recv) = self.shared.add_event_waiter(predicate);
+        match time::timeout(timeout, recv).await {
+            Ok(Ok(event)) => Ok(event),
+            Ok(Err(_)) => bail!(InternalError, "Event waiter was dropped."),
+            Err(_) => {
+                self.shared.cancel_event_waiter(id);
+                bail!(IoError, "Timed out waiting for gateway event.")
+            }
+        }
+    }
+
+    /// Collects every gateway event matching a predicate until a timeout expires, or until a
+    /// maximum number of events have been received.
+    ///
+    /// The predicate is checked as with [`GatewayController::wait_for`].
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use futures::StreamExt;
+    /// # use minnie::DiscordContext;
+    /// # use minnie::model::event::GatewayEvent;
+    /// # use minnie::model::types::MessageId;
+    /// # use std::time::Duration;
+    /// async fn count_reactions(ctx: DiscordContext, message: MessageId) -> usize {
+    ///     ctx.gateway().collect(move |ev| match ev {
+    ///         GatewayEvent::MessageReactionAdd(ev) => ev.message_id == message,
+    ///         _ => false,
+    ///     }, Duration::from_secs(60), Some(10)).collect::<Vec<_>>().await.len()
+    /// }
+    /// ```
+    pub fn collect(
+        &self,
+        predicate: impl Fn(&GatewayEvent) -> bool + Send + Sync + 'static,
+        timeout: Duration,
+        max_events: Option<usize>,
+    ) -> EventCollector {
+        let (id, recv) = self.shared.add_event_collector(predicate);
+        let mut collector = EventCollector {
+            id, recv,
+            shared: self.shared.clone(),
+            delay: Box::pin(time::delay_for(timeout)),
+            remaining: max_events,
+            finished: false,
+        };
+        if max_events == Some(0) {
+            collector.finish();
+        }
+        collector
+    }
+}
+
+/// A stream of gateway events matching a predicate.
+///
+/// Instances can be obtained via [`GatewayController::collect`]. The predicate is unregistered
+/// once the stream ends or is dropped.
+#[must_use = "streams do nothing unless polled"]
+pub struct EventCollector {
+    id: u64,
+    shared: Arc<shard::ManagerSharedState>,
+    recv: mpsc::UnboundedReceiver<GatewayEvent>,
+    delay: Pin<Box<time::Delay>>,
+    remaining: Option<usize>,
+    finished: bool,
+}
+impl EventCollector {
+    fn finish(&mut self) {
+        if !self.finished {
+            self.finished = true;
+            self.shared.cancel_event_collector(self.id);
+        }
+    }
+}
+impl Stream for EventCollector {
+    type Item = GatewayEvent;
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<GatewayEvent>> {
+        let this = &mut *self;
+        if this.finished {
+            return Poll::Ready(None)
+        }
+        match this.recv.poll_next_unpin(cx) {
+            Poll::Ready(Some(ev)) => {
+                if let Some(remaining) = &mut this.remaining {
+                    *remaining -= 1;
+                    if *remaining == 0 {
+                        this.finish();
+                    }
+                }
+                return Poll::Ready(Some(ev))
+            }
+            Poll::Ready(None) => {
+                this.finish();
+                return Poll::Ready(None)
+            }
+            Poll::Pending => { }
+        }
+        if this.delay.as_mut().poll(cx).is_ready() {
+            this.finish();
+            return Poll::Ready(None)
+        }
+        Poll::Pending
+    }
+}
+impl Drop for EventCollector {
+    fn drop(&mut self) {
+        self.finish();
+    }
 }
@@ -7,35 +7,192 @@ use crate::gateway::{
 use crate::ws::*;
 use crate::ws::Response::*;
 use crossbeam_channel::{self, Receiver, Sender};
+use futures::channel::{mpsc, oneshot};
+use fxhash::{FxHashMap, FxHashSet};
+use minnie_errors::*;
 use minnie_model::event::*;
 use minnie_model::gateway::*;
+use minnie_model::guild::Member;
 use minnie_model::types::*;
-use parking_lot::RwLock;
+use parking_lot::{Mutex, RwLock};
+use std::collections::VecDeque;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::time::{Duration, Instant};
 use tokio::runtime::Handle;
 use tokio::time;
 use tracing_futures::*;
 use url::*;
 
+/// The number of commands Discord allows a shard to send per rate limit window.
+const COMMAND_LIMIT: u32 = 120;
+/// The number of commands per window reserved for heartbeats and identifying.
+const COMMAND_LIMIT_RESERVED: u32 = 5;
+/// The length of the gateway command rate limit window.
+const COMMAND_LIMIT_WINDOW: Duration = Duration::from_secs(60);
+/// How long each identify rate limit bucket must wait between identifies.
+const IDENTIFY_INTERVAL: Duration = Duration::from_secs(5);
+
 #[derive(Clone)]
 enum ShardSignal {
     SendPresenceUpdate,
     SendRequestGuildMembers(GuildMembersRequest),
-    Reconnect,
+    #[cfg(feature = "unstable")]
+    SendRawPacket(GatewayOpcode, serde_json::Value),
+}
+
+/// Tracks when a shard sent its recent commands, so that no more than the allowed number are
+/// sent within any rate limit window, rather than only within fixed windows.
+struct CommandLimiter {
+    sent: VecDeque<Instant>,
+}
+impl CommandLimiter {
+    fn new() -> Self {
+        CommandLimiter { sent: VecDeque::new() }
+    }
+
+    /// Attempts to take a command from the budget for the window ending now.
+    fn try_acquire(&mut self) -> bool {
+        let now = Instant::now();
+        while let Some(&oldest) = self.sent.front() {
+            if oldest + COMMAND_LIMIT_WINDOW > now {
+                break
+            }
+            self.sent.pop_front();
+        }
+        if self.sent.len() < (COMMAND_LIMIT - COMMAND_LIMIT_RESERVED) as usize {
+            self.sent.push_back(now);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+/// A guild members request waiting for all chunks to be received.
+struct PendingMemberRequest {
+    members: Vec<Member>,
+    send: oneshot::Sender<Vec<Member>>,
+}
+
+/// A predicate waiting for a matching gateway event.
+struct EventWaiter {
+    predicate: Box<dyn Fn(&GatewayEvent) -> bool + Send + Sync>,
+    send: oneshot::Sender<GatewayEvent>,
+}
+
+/// A predicate collecting every matching gateway event.
+struct EventCollectorEntry {
+    predicate: Box<dyn Fn(&GatewayEvent) -> bool + Send + Sync>,
+    send: mpsc::UnboundedSender<GatewayEvent>,
 }
 
 /// Contains state that persists across an entire Discord context.
 pub struct ManagerSharedState {
     pub presence: RwLock<PresenceUpdate>,
     pub config: RwLock<GatewayConfig>,
+    member_requests: Mutex<FxHashMap<String, PendingMemberRequest>>,
+    event_waiters: Mutex<FxHashMap<u64, EventWaiter>>,
+    event_collectors: Mutex<FxHashMap<u64, EventCollectorEntry>>,
+    next_waiter_id: AtomicU64,
 }
 impl ManagerSharedState {
     pub fn new(presence: PresenceUpdate, config: GatewayConfig) -> Self {
         ManagerSharedState {
             presence: RwLock::new(presence),
             config: RwLock::new(config),
+            member_requests: Mutex::new(FxHashMap::default()),
+            event_waiters: Mutex::new(FxHashMap::default()),
+            event_collectors: Mutex::new(FxHashMap::default()),
+            next_waiter_id: AtomicU64::new(0),
+        }
+    }
+
+    /// Registers a guild members request, returning a channel that receives every member once
+    /// the final chunk is received.
+    pub fn add_member_request(&self, nonce: String) -> oneshot::Receiver<Vec<Member>> {
+        let (send, recv) = oneshot::channel();
+        self.member_requests.lock().insert(nonce, PendingMemberRequest {
+            members: Vec::new(),
+            send,
+        });
+        recv
+    }
+    pub fn cancel_member_request(&self, nonce: &str) {
+        self.member_requests.lock().remove(nonce);
+    }
+    pub fn clear_member_requests(&self) {
+        self.member_requests.lock().clear();
+    }
+    fn handle_members_chunk(&self, ev: &GuildMembersChunkEvent) {
+        if let Some(nonce) = &ev.nonce {
+            let mut requests = self.member_requests.lock();
+            if let Some(request) = requests.get_mut(nonce) {
+                request.members.extend(ev.members.iter().cloned());
+                if ev.is_last_chunk() {
+                    let request = requests.remove(nonce).unwrap();
+                    let _ = request.send.send(request.members);
+                }
+            }
+        }
+    }
+
+    /// Registers a predicate, returning an ID that can be used to cancel it, and a channel that
+    /// receives the first event matching it.
+    pub fn add_event_waiter(
+        &self, predicate: impl Fn(&GatewayEvent) -> bool + Send + Sync + 'static,
+    ) -> (u64, oneshot::Receiver<GatewayEvent>) {
+        let id = self.next_waiter_id.fetch_add(1, Ordering::Relaxed);
+        let (send, recv) = oneshot::channel();
+        self.event_waiters.lock().insert(id, EventWaiter {
+            predicate: Box::new(predicate),
+            send,
+        });
+        (id, recv)
+    }
+    pub fn cancel_event_waiter(&self, id: u64) {
+        self.event_waiters.lock().remove(&id);
+    }
+
+    /// Registers a predicate, returning an ID that can be used to cancel it, and a channel that
+    /// receives every event matching it.
+    pub fn add_event_collector(
+        &self, predicate: impl Fn(&GatewayEvent) -> bool + Send + Sync + 'static,
+    ) -> (u64, mpsc::UnboundedReceiver<GatewayEvent>) {
+        let id = self.next_waiter_id.fetch_add(1, Ordering::Relaxed);
+        let (send, recv) = mpsc::unbounded();
+        self.event_collectors.lock().insert(id, EventCollectorEntry {
+            predicate: Box::new(predicate),
+            send,
+        });
+        (id, recv)
+    }
+    pub fn cancel_event_collector(&self, id: u64) {
+        self.event_collectors.lock().remove(&id);
+    }
+
+    fn handle_event_waiters(&self, ev: &GatewayEvent) {
+        {
+            let mut waiters = self.event_waiters.lock();
+            if !waiters.is_empty() {
+                let matched: Vec<u64> = waiters.iter()
+                    .filter(|(_, waiter)| (waiter.predicate)(ev))
+                    .map(|(id, _)| *id)
+                    .collect();
+                for id in matched {
+                    let waiter = waiters.remove(&id).unwrap();
+                    let _ = waiter.send.send(ev.clone());
+                }
+            }
+        }
+        {
+            let mut collectors = self.event_collectors.lock();
+            if !collectors.is_empty() {
+                // Collectors whose stream was dropped are removed here as well.
+                collectors.retain(|_, collector| {
+                    !(collector.predicate)(ev) || collector.send.unbounded_send(ev.clone()).is_ok()
+                });
+            }
         }
     }
 }
@@ -43,26 +200,32 @@ impl ManagerSharedState {
 /// Contains state that persists across an entire gateway connection.
 pub struct GatewayState {
     is_shutdown: AtomicBool,
+    is_draining: AtomicBool,
     gateway_url: Url,
     compress: CompressionType,
+    /// The earliest time each identify rate limit bucket may identify again.
+    identify_buckets: Vec<Mutex<Instant>>,
     shared: Arc<ManagerSharedState>,
 }
 impl GatewayState {
-    pub fn new(base_url: &str, shared: Arc<ManagerSharedState>) -> Self {
+    pub fn new(base_url: &str, max_concurrency: u32, shared: Arc<ManagerSharedState>) -> Self {
         let config = shared.config.read().clone();
 
         let mut gateway_url = Url::parse(base_url).expect("Could not parse gateway URL.");
-        let full_path = format!("v=6&encoding=json{}",
+        let full_path = format!("v=9&encoding=json{}",
                                 if config.compress == CompressionType::TransportCompression {
                                     "&compress=zlib-stream"
                                 } else {
                                     ""
                                 });
         gateway_url.set_query(Some(&full_path));
+        let now = Instant::now();
 
         GatewayState {
             is_shutdown: AtomicBool::new(false),
+            is_draining: AtomicBool::new(false),
             compress: config.compress,
+            identify_buckets: (0..max_concurrency.max(1)).map(|_| Mutex::new(now)).collect(),
             shared: shared.clone(),
             gateway_url,
         }
@@ -70,6 +233,28 @@ impl GatewayState {
     pub fn shutdown(&self) {
         self.is_shutdown.store(true, Ordering::Relaxed)
     }
+    /// Signals shards to send any queued packets, then disconnect.
+    pub fn drain(&self) {
+        self.is_draining.store(true, Ordering::Relaxed)
+    }
+    fn is_draining(&self) -> bool {
+        self.is_draining.load(Ordering::Relaxed)
+    }
+
+    /// Waits until the given shard is allowed to identify, reserving the identify for it.
+    ///
+    /// Shards in the same rate limit bucket are spaced out by [`IDENTIFY_INTERVAL`], in the
+    /// order they call this method.
+    async fn wait_for_identify(&self, id: ShardId) {
+        let at = {
+            let bucket = &self.identify_buckets[id.0 as usize % self.identify_buckets.len()];
+            let mut next = bucket.lock();
+            let at = (*next).max(Instant::now());
+            *next = at + IDENTIFY_INTERVAL;
+            at
+        };
+        time::delay_until(at.into()).await;
+    }
 }
 
 /// A handle representing the state of a running shard.
@@ -78,19 +263,31 @@ pub struct ShardState {
     started: AtomicBool,
     is_shutdown: AtomicBool,
     is_connected: AtomicBool,
+    unavailable_guilds: Mutex<FxHashSet<GuildId>>,
+    /// Commands waiting to be sent, as the shard is not connected or is being rate limited.
     send: Sender<ShardSignal>,
     recv: Receiver<ShardSignal>,
+    /// Whether a presence update is waiting to be sent. This is kept out of the command queue,
+    /// as the presence is read when the update is sent, so only one update is ever needed.
+    presence_outdated: AtomicBool,
+    reconnect_requested: AtomicBool,
+    command_limiter: Mutex<CommandLimiter>,
     gateway: Arc<GatewayState>,
 }
 impl ShardState {
-    pub fn new(id: ShardId, shared: Arc<GatewayState>) -> ShardState {
-        let (send, recv) = crossbeam_channel::unbounded();
+    pub fn new(id: ShardId, shared: Arc<GatewayState>, queue_capacity: usize) -> ShardState {
+        // A zero capacity channel is a rendezvous channel, which would reject every command.
+        let (send, recv) = crossbeam_channel::bounded(queue_capacity.max(1));
         ShardState {
             id, send, recv,
             gateway: shared,
             started: AtomicBool::new(false),
             is_shutdown: AtomicBool::new(false),
             is_connected: AtomicBool::new(false),
+            unavailable_guilds: Mutex::new(FxHashSet::default()),
+            presence_outdated: AtomicBool::new(false),
+            reconnect_requested: AtomicBool::new(false),
+            command_limiter: Mutex::new(CommandLimiter::new()),
         }
     }
 
@@ -102,13 +299,64 @@ impl ShardState {
     }
 
     pub fn reconnect(&self) {
-        self.send.send(ShardSignal::Reconnect).unwrap();
+        self.reconnect_requested.store(true, Ordering::Relaxed);
     }
     pub fn notify_update_presence(&self) {
-        self.send.send(ShardSignal::SendPresenceUpdate).unwrap();
+        self.presence_outdated.store(true, Ordering::Relaxed);
+    }
+    pub fn request_guild_members(&self, request: GuildMembersRequest) -> Result<()> {
+        self.queue_command(ShardSignal::SendRequestGuildMembers(request))
+    }
+    #[cfg(feature = "unstable")]
+    pub fn send_raw_packet(&self, op: GatewayOpcode, data: serde_json::Value) -> Result<()> {
+        self.queue_command(ShardSignal::SendRawPacket(op, data))
+    }
+
+    /// Tracks which guilds are unavailable, so that a `Guild Create` event for them can be
+    /// distinguished from the bot joining a new guild.
+    fn track_unavailable_guilds(&self, event: &GatewayEvent) {
+        match event {
+            GatewayEvent::Ready(ev) => {
+                let mut guilds = self.unavailable_guilds.lock();
+                guilds.clear();
+                guilds.extend(ev.guilds.iter().filter(|x| x.unavailable).map(|x| x.id));
+            }
+            GatewayEvent::GuildDelete(ev) if ev.0.unavailable => {
+                self.unavailable_guilds.lock().insert(ev.0.id);
+            }
+            _ => { }
+        }
+    }
+    fn take_unavailable_guild(&self, id: GuildId) -> bool {
+        self.unavailable_guilds.lock().remove(&id)
     }
-    pub fn request_guild_members(&self, request: GuildMembersRequest) {
-        self.send.send(ShardSignal::SendRequestGuildMembers(request)).unwrap();
+
+    /// Queues a command to be sent once the rate limit allows it, failing if the queue is full.
+    fn queue_command(&self, command: ShardSignal) -> Result<()> {
+        if self.send.try_send(command).is_err() {
+            bail!(GatewayQueueFull, self.id.0, self.id.1);
+        }
+        Ok(())
+    }
+
+    /// Returns whether any commands are waiting to be sent.
+    fn has_pending_commands(&self) -> bool {
+        self.presence_outdated.load(Ordering::Relaxed) || !self.recv.is_empty()
+    }
+
+    /// Takes the next queued command, if the rate limit allows one to be sent.
+    fn next_command(&self) -> Option<ShardSignal> {
+        if !self.presence_outdated.load(Ordering::Relaxed) && self.recv.is_empty() {
+            return None
+        }
+        if !self.command_limiter.lock().try_acquire() {
+            return None
+        }
+        if self.presence_outdated.swap(false, Ordering::Relaxed) {
+            Some(ShardSignal::SendPresenceUpdate)
+        } else {
+            self.recv.try_recv().ok()
+        }
     }
 }
 
@@ -159,6 +407,7 @@ async fn running_shard(
     config: GatewayConfig,
     shard: &ShardState,
     session: &mut ShardSession,
+    connected: &mut Option<bool>,
     dispatch: &impl GatewayHandler,
 ) -> ShardStatus {
     use self::ShardPhase::*;
@@ -201,6 +450,15 @@ async fn running_shard(
         ($error:expr, false $(,)?) => { emit_err!(@emit $error, emit_err!(@ret_success)); };
     }
 
+    // Wait for our turn to identify before connecting, as Discord expects a heartbeat soon after
+    // the connection is opened.
+    let mut identify_reserved = false;
+    if let ShardSession::Inactive = session {
+        shard.gateway.wait_for_identify(shard.id).await;
+        identify_reserved = true;
+        check_shutdown!();
+    }
+
     // Connect to the gateway
     let url = shard.gateway.gateway_url.clone();
     let compress = shard.gateway.compress == CompressionType::TransportCompression;
@@ -209,36 +467,48 @@ async fn running_shard(
         Err(e) => emit_err!(GatewayError::ConnectionError(e)),
     };
     macro_rules! send {
-        ($packet_op:ident, $seq:expr, $data:expr) => {{
+        (@raw $op:expr, $seq:expr, $data:expr) => {{
             check_shutdown!();
             let packet = GatewayPacketFrame {
-                op: GatewayOpcode::$packet_op.to_i128(),
+                op: $op.to_i128(),
                 s: $seq,
                 d: $data,
             };
             if let Err(e) = conn.send(&packet).await {
                 emit_err!(GatewayError::WebsocketSendError(e));
             }
-        }}
+        }};
+        ($packet_op:ident, $seq:expr, $data:expr) => {
+            send!(@raw GatewayOpcode::$packet_op, $seq, $data)
+        };
     }
 
     // Start processing gateway events
+    *shard.command_limiter.lock() = CommandLimiter::new();
     let mut conn_phase = Initial;
     let conn_start = Instant::now();
-    let mut last_heartbeat = Instant::now();
+    let mut next_heartbeat = Instant::now();
     let mut heartbeat_interval = Duration::from_secs(0);
     let mut heartbeat_ack = false;
+    let mut last_packet = Instant::now();
     loop {
         check_shutdown!();
 
         // Try to read a packet from the gateway for one second, before processing other tasks.
         let mut need_connect = false;
-        match conn.receive(|s| GatewayPacket::from_json(s, |t|
-            dispatch.ignores_event(gateway_ctx, t)
-        ), Duration::from_secs(1)).await {
+        let received = conn.receive(|s| GatewayPacket::from_json(
+            s, |t| dispatch.ignores_event(gateway_ctx, t), config.keep_unknown_payloads,
+        ), Duration::from_secs(1)).await;
+        if let Ok(Packet(_)) = &received {
+            last_packet = Instant::now();
+        }
+        match received {
             Ok(Packet(GatewayPacket::Hello(packet))) if conn_phase == Initial => {
                 heartbeat_interval = packet.heartbeat_interval;
                 heartbeat_ack = true;
+                // Discord asks that the first heartbeat be sent after a random fraction of the
+                // interval, so that shards reconnecting at once do not heartbeat in lockstep.
+                next_heartbeat = Instant::now() + heartbeat_interval.mul_f64(rand::random::<f64>());
                 need_connect = true;
             }
             Ok(Packet(GatewayPacket::InvalidSession(can_resume))) if conn_phase != Initial => {
@@ -254,16 +524,47 @@ async fn running_shard(
             }
             Ok(Packet(GatewayPacket::Dispatch(seq, t, data))) if conn_phase != Initial => {
                 check_shutdown!();
-                conn_phase = Connected; // We assume we connected successfully if we got any event.
                 conn_successful = true;
-                shard.is_connected.store(true, Ordering::Relaxed);
+                // When resuming, Discord replays missed events before sending `Resumed`, so the
+                // connection is only fully established once that event is received. Otherwise,
+                // we assume we connected successfully if we got any event.
+                let is_resumed = matches!(&data, Some(GatewayEvent::Resumed));
+                if conn_phase == Authenticating || (conn_phase == Resuming && is_resumed) {
+                    let resumed = conn_phase == Resuming;
+                    *connected = Some(resumed);
+                    conn_phase = Connected;
+                    shard.is_connected.store(true, Ordering::Relaxed);
+                    let result = minnie_errors::catch_panic(|| {
+                        dispatch.on_connect(gateway_ctx, resumed);
+                        Ok(())
+                    });
+                    if let Err(e) = result {
+                        emit_err!(GatewayError::EventHandlingPanicked(e), true);
+                    }
+                }
                 if let Some(data) = data {
                     if let GatewayEvent::Ready(ev) = &data {
                         *session = ShardSession::Resume(ev.session_id.clone(), seq);
+                        gateway_ctx.ctx.set_current_user(ev.user.clone());
                     } else {
                         session.set_sequence_id(seq);
                     }
-                    match minnie_errors::catch_panic(|| Ok(dispatch.on_event(gateway_ctx, data))) {
+                    if let GatewayEvent::UserUpdate(ev) = &data {
+                        gateway_ctx.ctx.update_current_user(&ev.0);
+                    }
+                    if let GatewayEvent::GuildMembersChunk(ev) = &data {
+                        shard.gateway.shared.handle_members_chunk(ev);
+                    }
+                    shard.gateway.shared.handle_event_waiters(&data);
+                    shard.track_unavailable_guilds(&data);
+                    #[cfg(feature = "cache")]
+                    gateway_ctx.ctx.data.cache.update(&data);
+                    let result = minnie_errors::catch_panic(|| Ok(match data {
+                        GatewayEvent::GuildCreate(ev) if shard.take_unavailable_guild(ev.0.id) =>
+                            dispatch.on_guild_available(gateway_ctx, ev),
+                        data => dispatch.on_event(gateway_ctx, data),
+                    }));
+                    match result {
                         Ok(Err(e)) => emit_err!(GatewayError::EventHandlingFailed(e), true),
                         Err(e) => emit_err!(GatewayError::EventHandlingPanicked(e), true),
                         _ => { }
@@ -276,14 +577,19 @@ async fn running_shard(
             }
             Ok(Packet(GatewayPacket::Reconnect)) => {
                 info!("Discord requested shard #{} to reconnect.", shard.id);
+                // The session is kept, so the shard resumes once it reconnects.
                 return ShardStatus::Reconnect
             },
             Ok(Packet(GatewayPacket::HeartbeatAck)) => heartbeat_ack = true,
-            Ok(Packet(GatewayPacket::UnknownOpcode(v))) =>
-                emit_err!(GatewayError::UnknownOpcode(v), true),
+            Ok(Packet(GatewayPacket::UnknownOpcode(v, data))) =>
+                emit_err!(GatewayError::UnknownOpcode(v, data), true),
             Ok(Packet(packet)) => emit_err!(GatewayError::UnexpectedPacket(packet), true),
             Ok(TimeoutEncountered) => { }
             Ok(ParseError(e)) => emit_err!(GatewayError::PacketParseFailed(e)),
+            Ok(Disconnected(Some(frame))) if u16::from(frame.code) == 4010 =>
+                emit_err!(GatewayError::InvalidShard(frame)),
+            Ok(Disconnected(Some(frame))) if u16::from(frame.code) == 4011 =>
+                emit_err!(GatewayError::ShardingRequired(frame)),
             Ok(Disconnected(e)) => emit_err!(GatewayError::RemoteHostDisconnected(e)),
             Err(e) => emit_err!(GatewayError::WebsocketError(e)),
         }
@@ -292,13 +598,21 @@ async fn running_shard(
         if need_connect {
             match session {
                 ShardSession::Inactive => {
+                    if !identify_reserved {
+                        shard.gateway.wait_for_identify(shard.id).await;
+                        check_shutdown!();
+                    }
+                    identify_reserved = false;
                     info!("Identifying on shard #{}", shard.id);
                     let pkt = PacketIdentify {
                         token: gateway_ctx.ctx.data.client_token.clone(),
-                        properties: ConnectionProperties {
-                            os: std::env::consts::OS.to_string(),
-                            browser: gateway_ctx.ctx.data.library_name.to_string(),
-                            device: gateway_ctx.ctx.data.library_name.to_string()
+                        properties: match &config.connection_properties {
+                            Some(properties) => properties.clone(),
+                            None => ConnectionProperties {
+                                os: std::env::consts::OS.to_string(),
+                                browser: gateway_ctx.ctx.data.library_name.to_string(),
+                                device: gateway_ctx.ctx.data.library_name.to_string()
+                            },
                         },
                         compress: shard.gateway.compress == CompressionType::PacketCompression,
                         large_threshold: Some(150),
@@ -324,29 +638,31 @@ async fn running_shard(
             }
         }
 
-        // Check the signal channel.
-        let mut do_reconnect = false;
-        let mut do_presence_update = false;
-        let mut member_request_packets = Vec::new();
-        while let Ok(sig) = shard.recv.try_recv() {
-            match sig {
-                ShardSignal::SendPresenceUpdate =>
-                    do_presence_update = true,
-                ShardSignal::SendRequestGuildMembers(packet) =>
-                    member_request_packets.push(packet),
-                ShardSignal::Reconnect =>
-                    do_reconnect = true,
-            }
-        }
-        if do_reconnect {
+        // Check for signals from the gateway controller.
+        let do_reconnect = shard.reconnect_requested.swap(false, Ordering::Relaxed);
+        let draining = shard.gateway.is_draining();
+        if do_reconnect && !draining {
             *session = ShardSession::Inactive;
             return ShardStatus::Reconnect;
         }
-        if do_presence_update {
-            send!(StatusUpdate, None, shard.gateway.shared.presence.read().clone());
+        // Send queued commands, leaving the rest queued if the rate limit is exhausted.
+        if conn_phase == Connected {
+            while let Some(command) = shard.next_command() {
+                match command {
+                    ShardSignal::SendPresenceUpdate =>
+                        send!(StatusUpdate, None, shard.gateway.shared.presence.read().clone()),
+                    ShardSignal::SendRequestGuildMembers(packet) =>
+                        send!(RequestGuildMembers, None, packet),
+                    #[cfg(feature = "unstable")]
+                    ShardSignal::SendRawPacket(op, data) =>
+                        send!(@raw op, None, data),
+                }
+            }
         }
-        for packet in member_request_packets {
-            send!(RequestGuildMembers, None, packet);
+        // While draining, keep the connection open until every queued command has been sent.
+        // `disconnect_graceful` forces a shutdown if this takes longer than its timeout.
+        if draining && !shard.has_pending_commands() {
+            return ShardStatus::Disconnect;
         }
 
         // Check various timers.
@@ -356,14 +672,23 @@ async fn running_shard(
                 emit_err!(GatewayError::HelloTimeout);
             }
         } else {
+            // Check whether the connection has stopped delivering packets entirely.
+            let now = Instant::now();
+            let idle_timeout = config.idle_timeout
+                .unwrap_or(heartbeat_interval * 2 + config.heartbeat_timeout_grace);
+            if last_packet + idle_timeout <= now {
+                emit_err!(GatewayError::IdleTimeout);
+            }
+
             // Check for heartbeats.
-            if last_heartbeat + heartbeat_interval < Instant::now() {
-                if !heartbeat_ack {
+            if next_heartbeat < now {
+                if heartbeat_ack {
+                    send!(Heartbeat, session.sequence_id(), None::<()>);
+                    next_heartbeat = now + heartbeat_interval;
+                    heartbeat_ack = false;
+                } else if next_heartbeat + config.heartbeat_timeout_grace <= now {
                     emit_err!(GatewayError::HeartbeatTimeout);
                 }
-                send!(Heartbeat, session.sequence_id(), None::<()>);
-                last_heartbeat = Instant::now();
-                heartbeat_ack = false;
             }
         }
     }
@@ -378,11 +703,25 @@ async fn shard_main_loop(
     let mut reconnect_delay = shard.gateway.shared.config.read().backoff_initial;
     let mut session = ShardSession::Inactive;
     loop {
+        if shard.gateway.is_draining() && !shard.has_pending_commands() {
+            info!("Shard #{} disconnected.", shard.id);
+            return
+        }
         let config = shard.gateway.shared.config.read().clone();
+        let mut connected = None;
         let result = running_shard(
-            &gateway_ctx, config, shard, &mut session, dispatch,
+            gateway_ctx, config, shard, &mut session, &mut connected, dispatch,
         ).await;
         shard.is_connected.store(false, Ordering::Relaxed);
+        if let Some(resumed) = connected {
+            let result = minnie_errors::catch_panic(|| {
+                dispatch.on_disconnect(gateway_ctx, resumed);
+                Ok(())
+            });
+            if let Err(e) = result {
+                dispatch.report_error(gateway_ctx, GatewayError::EventHandlingPanicked(e));
+            }
+        }
 
         let config = shard.gateway.shared.config.read().clone();
         match result {
@@ -401,7 +740,7 @@ async fn shard_main_loop(
             ShardStatus::ReconnectWithBackoff => {
                 info!("Waiting {} seconds before reconnecting shard #{}...",
                       reconnect_delay.as_millis() as f32 / 1000.0, shard.id);
-                time::delay_for(reconnect_delay.into()).await;
+                time::delay_for(reconnect_delay).await;
                 let variation = config.backoff_variation.unwrap_or(Duration::from_secs(0));
                 let f32_secs =
                     reconnect_delay.as_secs_f64() * config.backoff_factor +
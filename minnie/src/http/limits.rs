@@ -1,9 +1,10 @@
-use crate::http::{SENTINEL, HttpConfig};
+use crate::http::{SENTINEL, HttpConfig, RateLimitCallback, RateLimitInfo};
 use fxhash::FxHashMap;
 use minnie_errors::*;
 use minnie_model::http::RateLimited;
 use minnie_model::types::Snowflake;
 use parking_lot::Mutex;
+use percent_encoding::{AsciiSet, CONTROLS, utf8_percent_encode};
 use std::cmp::{max, min};
 use std::fmt;
 use std::panic::{AssertUnwindSafe, resume_unwind};
@@ -384,19 +385,40 @@ enum ResponseStatus {
     RateLimited(Option<RateLimitHeaders>, Duration),
     GloballyRateLimited(Duration),
 }
+/// The maximum length of an audit log reason, in characters.
+const MAX_REASON_LENGTH: usize = 512;
+
+/// Characters that are percent-encoded in audit log reasons.
+const REASON_ENCODE_SET: &AsciiSet = &CONTROLS
+    .add(b' ').add(b'"').add(b'#').add(b'%').add(b'<').add(b'>').add(b'?')
+    .add(b'`').add(b'{').add(b'}');
+
+/// Validates an audit log reason, and encodes it for use in the `X-Audit-Log-Reason` header.
+fn encode_reason(reason: &str) -> Result<HeaderValue> {
+    ensure!(
+        reason.chars().count() <= MAX_REASON_LENGTH,
+        InvalidInput, "Audit log reasons may be at most 512 characters long.",
+    );
+    let encoded = utf8_percent_encode(reason, REASON_ENCODE_SET).to_string();
+    HeaderValue::from_str(&encoded).internal_err("Encoded audit log reason is not a valid header.")
+}
+
 async fn check_response<'a>(
     request: RequestBuilder,
-    reason: &'a Option<String>,
+    reason: &'a Option<HeaderValue>,
     client_token: &'a HeaderValue,
     call_name: &'static str,
 ) -> Result<ResponseStatus> {
-    let mut request = request
-        .header("X-RateLimit-Precision", "millisecond")
-        .header("Authorization", client_token);
+    let mut request = request.header("Authorization", client_token);
     if let Some(reason) = &reason {
         request = request.header("X-Audit-Log-Reason", reason);
     }
     let response = request.send().await.io_err("Failed to make API request.")?;
+    classify_response(response, call_name).await
+}
+
+/// Checks whether a response was successful or rate limited.
+async fn classify_response(response: Response, call_name: &'static str) -> Result<ResponseStatus> {
     if response.status().is_success() {
         let rate_info = parse_headers(response.headers())?;
         Ok(ResponseStatus::Success(rate_info, response))
@@ -405,7 +427,11 @@ async fn check_response<'a>(
         let rate_info = response.json::<RateLimited>().await
             .context(ErrorKind::DiscordBadResponse("Could not parse rate limit information."))?;
         debug!("Encountered rate limit: {:?}", rate_info);
-        if rate_info.global {
+        let global_header = parse_header::<bool>(&headers_tmp, "X-RateLimit-Global")?;
+        if rate_info.global || global_header.unwrap_or(false) {
+            // A global rate limit applies to every route, so this is pushed to the shared
+            // `GlobalLimit` that all calls check before being sent.
+            debug!("Encountered global rate limit, blocking all routes.");
             Ok(ResponseStatus::GloballyRateLimited(rate_info.retry_after))
         } else {
             Ok(ResponseStatus::RateLimited(parse_headers(&headers_tmp)?, rate_info.retry_after))
@@ -420,6 +446,35 @@ async fn check_response<'a>(
     }
 }
 
+/// Reports rate limit information to the user-provided callback, if any.
+fn report_limits(
+    callback: &Option<RateLimitCallback>, headers: &Option<RateLimitHeaders>,
+    call_name: &'static str,
+) {
+    if let (Some(callback), Some(headers)) = (callback, headers) {
+        callback(call_name, RateLimitInfo {
+            limit: headers.limit,
+            remaining: headers.remaining,
+            resets_at: headers.resets_at,
+            reset_after: headers.resets_in,
+            bucket: headers.bucket.clone(),
+        });
+    }
+}
+
+/// Checks whether a rate limited API call may be retried.
+fn check_retries(
+    retries: &mut u32, max_retries: Option<u32>, wait_duration: Duration, call_name: &'static str,
+) -> Result<()> {
+    if let Some(max_retries) = max_retries {
+        if *retries >= max_retries {
+            bail!(RateLimited, call_name, wait_duration);
+        }
+    }
+    *retries += 1;
+    Ok(())
+}
+
 #[derive(Debug)]
 pub struct RateLimitStore {
     config: HttpConfig,
@@ -494,6 +549,15 @@ impl RateLimitRoute {
         id: Snowflake,
         call_name: &'static str,
     ) -> Result<Response> {
+        let (max_retries, on_update) = {
+            let store = store.lock();
+            (store.config.max_rate_limit_retries, store.config.on_rate_limit_update.clone())
+        };
+        let reason = match reason {
+            Some(reason) => Some(encode_reason(&reason)?),
+            None => None,
+        };
+        let mut retries = 0;
         loop {
             let mut stored_bucket = None;
             if use_rate_limits {
@@ -503,15 +567,18 @@ impl RateLimitRoute {
                 trace!("Sending request...");
                 match check_response(make_request()?, &reason, &client_token, call_name).await? {
                     ResponseStatus::Success(rate_limit, response) => {
+                        report_limits(&on_update, &rate_limit, call_name);
                         if use_rate_limits {
                             self.update_limits(id, rate_limit, store);
                         }
                         Ok(Some(response))
                     }
                     ResponseStatus::RateLimited(rate_limit, wait_duration) => {
+                        report_limits(&on_update, &rate_limit, call_name);
                         if use_rate_limits {
                             self.update_limits(id, rate_limit, store);
                         }
+                        check_retries(&mut retries, max_retries, wait_duration, call_name)?;
                         wait_until(Instant::now() + wait_duration).await;
                         Ok(None)
                     }
@@ -520,6 +587,7 @@ impl RateLimitRoute {
                         if use_rate_limits {
                             push_global_rate_limit(global_limit, time);
                         }
+                        check_retries(&mut retries, max_retries, wait_duration, call_name)?;
                         wait_until(time).await;
                         Ok(None)
                     }
@@ -549,4 +617,62 @@ impl fmt::Debug for RateLimitRoute {
         drop(lock);
         f.write_str(")")
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RETRY_AFTER: Duration = Duration::from_millis(200);
+
+    fn too_many_requests(global_body: bool, global_header: bool) -> Response {
+        let body = format!(
+            r#"{{"message": "You are being rate limited.", "retry_after": {}, "global": {}}}"#,
+            RETRY_AFTER.as_secs_f64(), global_body,
+        );
+        let mut response = http::Response::builder().status(StatusCode::TOO_MANY_REQUESTS);
+        if global_header {
+            response = response.header("X-RateLimit-Global", "true");
+        }
+        Response::from(response.body(body).unwrap())
+    }
+
+    async fn check_global_limit_blocks_routes(global_body: bool, global_header: bool) {
+        let response = too_many_requests(global_body, global_header);
+        let wait_duration = match classify_response(response, "test").await.unwrap() {
+            ResponseStatus::GloballyRateLimited(wait_duration) => wait_duration,
+            status => panic!("Expected a global rate limit, got {:?}", status),
+        };
+        assert_eq!(wait_duration, RETRY_AFTER);
+
+        let global_limit = GlobalLimit::default();
+        let limited_until = Instant::now() + wait_duration;
+        push_global_rate_limit(&global_limit, limited_until);
+
+        // A different route with no rate limits of its own must still wait for the global limit.
+        let other_route = RateLimitRoute::default();
+        other_route.check_wait(SENTINEL, &global_limit).await;
+        assert!(Instant::now() >= limited_until);
+        assert!(global_limit.lock().is_none());
+    }
+
+    #[tokio::test]
+    async fn global_rate_limit_from_body() {
+        check_global_limit_blocks_routes(true, false).await;
+    }
+
+    #[tokio::test]
+    async fn global_rate_limit_from_header() {
+        check_global_limit_blocks_routes(false, true).await;
+    }
+
+    #[tokio::test]
+    async fn route_rate_limit_is_not_global() {
+        let response = too_many_requests(false, false);
+        match classify_response(response, "test").await.unwrap() {
+            ResponseStatus::RateLimited(None, wait_duration) =>
+                assert_eq!(wait_duration, RETRY_AFTER),
+            status => panic!("Expected a route rate limit, got {:?}", status),
+        }
+    }
+}
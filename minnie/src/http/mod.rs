@@ -3,20 +3,30 @@
 use crate::context::DiscordContext;
 use derive_setters::*;
 use enumset::*;
+use fxhash::FxHashMap;
 use minnie_errors::*;
+use minnie_model::audit_log::*;
 use minnie_model::channel::*;
 use minnie_model::guild::*;
+use minnie_model::interaction::*;
 use minnie_model::message::*;
 use minnie_model::types::*;
 use minnie_model::user::*;
 use parking_lot::Mutex;
-use reqwest::header::HeaderValue;
+use reqwest::{Body, Client, Method, RequestBuilder};
+use reqwest::header::{HeaderValue, USER_AGENT};
 use reqwest::multipart::{Form, Part};
 use serde::*;
+use serde::de::DeserializeOwned;
 use serde_json;
 use std::error::{Error as StdError};
+use std::fs::File;
+use std::io::{Seek, SeekFrom};
+use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
+use tokio::io::AsyncReadExt;
+use tokio_util::codec::{BytesCodec, FramedRead};
 use tracing_futures::*;
 
 mod limits;
@@ -26,8 +36,31 @@ pub use minnie_model::http::*;
 
 const SENTINEL: Snowflake = Snowflake(0);
 
+/// The rate limit information returned alongside a response from Discord.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct RateLimitInfo {
+    /// The number of API calls that can be made in the current period.
+    pub limit: u32,
+    /// The number of API calls remaining in the current period.
+    pub remaining: u32,
+    /// When the rate limit resets.
+    pub resets_at: SystemTime,
+    /// How long until the rate limit resets.
+    pub reset_after: Duration,
+    /// A unique string identifying the rate limit bucket.
+    pub bucket: String,
+}
+
+/// The default maximum total size of files attached to a single message, in bytes.
+pub const DEFAULT_MAX_UPLOAD_SIZE: u64 = 8 * 1024 * 1024;
+
+/// A callback invoked with the name of an API call and its rate limit information.
+pub type RateLimitCallback = Arc<dyn Fn(&str, RateLimitInfo) + Send + Sync>;
+
 /// Stores settings for a gateway.
-#[derive(Clone, Debug, Setters)]
+#[derive(Clone, Derivative, Setters)]
+#[derivative(Debug)]
 #[non_exhaustive]
 pub struct HttpConfig {
     /// The maximum amount of time an expired rate limit is allowed to remain for.
@@ -40,6 +73,25 @@ pub struct HttpConfig {
     pub reallocate_caches_period: Duration,
     /// The maximum amount of time to wait on rate limits to update from in-progress API calls.
     pub max_wait_for_active: Duration,
+    /// The maximum number of times to retry an API call that was rate limited by Discord.
+    ///
+    /// If this is exceeded, the API call fails with an error, and
+    /// [`Error::retry_after`](`minnie_errors::Error::retry_after`) can be used to check when
+    /// it may be retried. If this is `None`, rate limited API calls are retried indefinitely.
+    #[setters(strip_option)]
+    pub max_rate_limit_retries: Option<u32>,
+    /// A callback invoked whenever rate limit information is received from Discord.
+    ///
+    /// This is intended for logging or exporting metrics, and should return quickly.
+    #[setters(strip_option)]
+    #[derivative(Debug="ignore")]
+    pub on_rate_limit_update: Option<RateLimitCallback>,
+    /// The maximum total size of files attached to a single message, in bytes.
+    ///
+    /// Messages with attachments exceeding this size are rejected before being sent to Discord.
+    /// This defaults to [`DEFAULT_MAX_UPLOAD_SIZE`], and may be raised for bots that only post in
+    /// guilds with a boosted upload limit.
+    pub max_upload_size: u64,
 }
 impl HttpConfig {
     /// Creates a default http configuration.
@@ -55,6 +107,9 @@ impl Default for HttpConfig {
             estimated_limits_expiry: Duration::from_secs(60),
             reallocate_caches_period: Duration::from_secs(60 * 10),
             max_wait_for_active: Duration::from_secs_f32(0.5),
+            max_rate_limit_retries: None,
+            on_rate_limit_update: None,
+            max_upload_size: DEFAULT_MAX_UPLOAD_SIZE,
         }
     }
 }
@@ -64,13 +119,25 @@ pub(crate) struct RateLimits {
     global_limit: GlobalLimit,
     buckets_store: Mutex<RateLimitStore>,
     routes: RouteRateLimits,
+    /// Rate limits for routes authenticated by a token in their path, which Discord tracks
+    /// separately from the bot's own rate limits.
+    token_global_limit: GlobalLimit,
+    token_buckets_store: Mutex<RateLimitStore>,
+    token_routes: RouteRateLimits,
+    custom_routes: Mutex<FxHashMap<(Method, String), Arc<RateLimitRoute>>>,
+    max_upload_size: u64,
 }
 impl RateLimits {
     pub(crate) fn new(config: HttpConfig) -> Self {
         RateLimits {
+            max_upload_size: config.max_upload_size,
             global_limit: Default::default(),
-            buckets_store: Mutex::new(RateLimitStore::new(config)),
+            buckets_store: Mutex::new(RateLimitStore::new(config.clone())),
             routes: Default::default(),
+            token_global_limit: Default::default(),
+            token_buckets_store: Mutex::new(RateLimitStore::new(config)),
+            token_routes: Default::default(),
+            custom_routes: Default::default(),
         }
     }
 }
@@ -104,6 +171,9 @@ impl <'a> Routes<'a> {
     pub(crate) fn reason_internal(&mut self, reason: impl Into<String>) {
         self.reason = Some(reason.into());
     }
+    fn uses_bearer_token(&self) -> bool {
+        self.client_token.as_bytes().starts_with(b"Bearer ")
+    }
 
     /// Makes an API call using a Bearer token.
     ///
@@ -115,10 +185,79 @@ impl <'a> Routes<'a> {
     }
 
     /// Sets the reason for the API call. This is recorded in the audit log for many calls.
+    ///
+    /// Reasons may be at most 512 characters long. Longer reasons cause the API call to fail
+    /// with [`ErrorKind::InvalidInput`](`crate::ErrorKind::InvalidInput`).
     pub fn reason(mut self, reason: impl Into<String>) -> Self {
         self.reason_internal(reason);
         self
     }
+
+    /// Makes an API call to an endpoint that is not otherwise supported by this library.
+    ///
+    /// The path is relative to the API base URL, e.g. `/channels/123/messages`. The body, if
+    /// any, is sent as JSON. Rate limits are tracked for each method and endpoint, and
+    /// separately for each channel, guild or webhook ID, as with the routes this library
+    /// supports directly.
+    ///
+    /// Endpoints that return no content are parsed as if they returned `null`, so `()` or
+    /// [`Option`] may be used as the return type for them.
+    pub async fn custom<T: DeserializeOwned>(
+        self, method: Method, path: &str, body: Option<serde_json::Value>,
+    ) -> Result<T> {
+        ensure!(path.starts_with('/'), InvalidInput, "Custom API paths must start with `/`.");
+        let (template, rate_id) = custom_route_key(path);
+        let span = info_span!(
+            "custom",
+            id = API_CALL_COUNT.fetch_add(1, Ordering::Relaxed),
+            rate_id = tracing::field::Empty,
+            rate_limited = self.use_rate_limits,
+            bearer = self.uses_bearer_token(),
+            %path
+        );
+        if rate_id != SENTINEL {
+            span.record("rate_id", &tracing::field::display(rate_id));
+        }
+        let fut = async move {
+            let Routes { ctx, client_token, use_rate_limits, reason } = self;
+            let route = ctx.data.rate_limits.custom_routes.lock()
+                .entry((method.clone(), template))
+                .or_default()
+                .clone();
+            let client = ApiClient {
+                client: &ctx.data.http_client,
+                base_url: &ctx.data.api_base_url,
+                user_agent: &ctx.data.http_user_agent_header,
+                max_upload_size: ctx.data.rate_limits.max_upload_size,
+            };
+            let response = route.perform_rate_limited(
+                &ctx.data.rate_limits.global_limit,
+                &ctx.data.rate_limits.buckets_store,
+                use_rate_limits,
+                &|| {
+                    let request = client.request(method.clone(), path);
+                    Ok(match &body {
+                        Some(body) => request.json(body),
+                        None => request,
+                    })
+                },
+                reason,
+                client_token,
+                rate_id,
+                "custom",
+            ).await?;
+            let data = response.bytes().await.map_err(|x| {
+                Error::new_with_cause(ErrorKind::IoError("Failed to receive API response."), x)
+            })?;
+            let data: &[u8] = if data.is_empty() { b"null" } else { &data };
+            serde_json::from_slice(data).map_err(|x| {
+                let kind = ErrorKind::DiscordBadResponse("Could not parse API response.");
+                Error::new_with_cause(kind, x)
+            })
+        };
+
+        fut.instrument(span).await
+    }
 }
 
 macro_rules! routes_wrapper {
@@ -136,6 +275,9 @@ macro_rules! routes_wrapper {
         }
 
         /// Sets the reason for the API call. This is recorded in the audit log for many calls.
+        ///
+        /// Reasons may be at most 512 characters long. Longer reasons cause the API call to fail
+        /// with [`ErrorKind::InvalidInput`](`crate::ErrorKind::InvalidInput`).
         pub fn reason(mut $ident_self, reason: impl Into<String>) -> Self {
             {
                 let ptr = &mut $($routes_field)*;
@@ -146,6 +288,43 @@ macro_rules! routes_wrapper {
     }
 }
 
+/// Derives the key a custom API call's rate limits are tracked under, and its rate limit ID.
+///
+/// IDs, tokens, codes and emoji in the path are replaced with placeholders, so that every call
+/// to an endpoint shares one entry. The channel, guild or webhook ID is used as the rate limit
+/// ID, as Discord tracks the rate limits for each of them separately.
+fn custom_route_key(path: &str) -> (String, Snowflake) {
+    let path = path.split('?').next().unwrap_or(path);
+    let segments: Vec<&str> = path.split('/').collect();
+    let mut template = Vec::with_capacity(segments.len());
+    let mut rate_id = SENTINEL;
+    for (i, &segment) in segments.iter().enumerate() {
+        let prev = if i >= 1 { segments[i - 1] } else { "" };
+        let prev_prev = if i >= 2 { segments[i - 2] } else { "" };
+        let placeholder = if !segment.is_empty() && segment.bytes().all(|b| b.is_ascii_digit()) {
+            if rate_id == SENTINEL && ["channels", "guilds", "webhooks"].contains(&prev) {
+                rate_id = segment.parse().map(Snowflake).unwrap_or(SENTINEL);
+            }
+            "{id}"
+        } else if prev == "reactions" {
+            "{emoji}"
+        } else if prev == "invites" || prev == "templates" {
+            "{code}"
+        } else if prev_prev == "webhooks" || prev_prev == "interactions" {
+            "{token}"
+        } else {
+            segment
+        };
+        template.push(placeholder);
+    }
+    (template.join("/"), rate_id)
+}
+
+/// Derives the ID rate limits are tracked under for a route authenticated by a token.
+fn token_rate_id(id: Snowflake, token: &str) -> Snowflake {
+    Snowflake(fxhash::hash64(&(id, token)))
+}
+
 /// Hack to allow as_str to work with route!.
 trait AsStrForStr {
     fn as_str(&self) -> &str;
@@ -155,15 +334,54 @@ impl <'a> AsStrForStr for &'a str {
         *self
     }
 }
+impl AsStrForStr for String {
+    fn as_str(&self) -> &str {
+        self
+    }
+}
+
+/// The default base URL used for API calls.
+///
+/// This targets the same API version as the gateway connections made by this library.
+pub const DEFAULT_API_BASE_URL: &str = "https://discord.com/api/v9";
+
+/// Wraps the HTTP client to resolve routes relative to the configured API base URL.
+struct ApiClient<'a> {
+    client: &'a Client,
+    base_url: &'a str,
+    user_agent: &'a HeaderValue,
+    max_upload_size: u64,
+}
+impl <'a> ApiClient<'a> {
+    fn request(&self, method: Method, route: &str) -> RequestBuilder {
+        self.client.request(method, &format!("{}{}", self.base_url, route))
+            .header(USER_AGENT, self.user_agent)
+    }
+    fn get(&self, route: &str) -> RequestBuilder {
+        self.request(Method::GET, route)
+    }
+    fn post(&self, route: &str) -> RequestBuilder {
+        self.request(Method::POST, route)
+    }
+    fn put(&self, route: &str) -> RequestBuilder {
+        self.request(Method::PUT, route)
+    }
+    fn patch(&self, route: &str) -> RequestBuilder {
+        self.request(Method::PATCH, route)
+    }
+    fn delete(&self, route: &str) -> RequestBuilder {
+        self.request(Method::DELETE, route)
+    }
+}
 
 static API_CALL_COUNT: AtomicUsize = AtomicUsize::new(0);
 
 macro_rules! route {
     ($base:literal) => {
-        concat!("https://discordapp.com/api/v6", $base)
+        $base
     };
     ($base:literal $(, $val:expr)* $(,)?) => {
-        format!(concat!("https://discordapp.com/api/v6", $base), $($val,)*)
+        format!($base, $($val,)*)
     };
 }
 macro_rules! routes {
@@ -172,7 +390,8 @@ macro_rules! routes {
         route $name:ident(
             $($param:ident: $param_ty:ty),*
             $(, %$($param_hidden:ident: $param_hidden_ty:ty),*)? $(,)?
-        ) $(on $rate_id:ident)? $(-> $ty:ty)? {
+        ) $(on $rate_id:ident)? $(using $token_param:ident)? $(-> $ty:ty)? {
+            $(check: $check:expr;)*
             $(let $let_name:ident $(: $let_ty:ty)? = $let_expr:expr;)*
             $(request:
                 $method:ident($($route:tt)*) $(.json($json:expr))? $(.query($query:expr))? $(,)?
@@ -191,25 +410,62 @@ macro_rules! routes {
             pub async fn $name(
                 self, $($param: $param_ty,)* $($($param_hidden: $param_hidden_ty,)*)?
             ) -> Result<($($ty)?)> {
+                #[allow(unused_mut, unused_assignments)]
+                let mut rate_id: Snowflake = SENTINEL;
+                $(rate_id = $rate_id.into();)?
+                let use_rate_limits = self.use_rate_limits;
+
+                let span = info_span!(
+                    stringify!($name),
+                    id = API_CALL_COUNT.fetch_add(1, Ordering::Relaxed),
+                    rate_id = tracing::field::Empty,
+                    rate_limited = use_rate_limits,
+                    bearer = self.uses_bearer_token(),
+                    $(%$param,)*
+                );
+                if rate_id != SENTINEL {
+                    span.record("rate_id", &tracing::field::display(rate_id));
+                }
+
+                // Routes authenticated by a token in their path are not limited together with
+                // the bot's own API calls, and each token has rate limits of its own.
+                #[allow(unused_mut, unused_assignments)]
+                let mut uses_token = false;
+                $(
+                    rate_id = token_rate_id(rate_id, $token_param.as_str());
+                    uses_token = true;
+                )?
+
                 let fut = async move {
-                    #[allow(unused_mut, unused_assignments)]
-                    let mut rate_id: Snowflake = SENTINEL;
-                    $(rate_id = $rate_id.into();)?
+                    $($check?;)*
                     $(let $let_name $(: $let_ty)? = $let_expr;)*
                     $(let __route = route!($($route)*);)?
-                    let Routes { ctx, client_token, use_rate_limits, reason } = self;
-                    let mut _response = ctx.data.rate_limits.routes.$name.perform_rate_limited(
-                        &ctx.data.rate_limits.global_limit,
-                        &ctx.data.rate_limits.buckets_store,
+                    let Routes { ctx, client_token, reason, .. } = self;
+                    let _client = ApiClient {
+                        client: &ctx.data.http_client,
+                        base_url: &ctx.data.api_base_url,
+                        user_agent: &ctx.data.http_user_agent_header,
+                        max_upload_size: ctx.data.rate_limits.max_upload_size,
+                    };
+                    let limits = &ctx.data.rate_limits;
+                    let (global_limit, buckets_store, routes) = if uses_token {
+                        (&limits.token_global_limit, &limits.token_buckets_store,
+                         &limits.token_routes)
+                    } else {
+                        (&limits.global_limit, &limits.buckets_store, &limits.routes)
+                    };
+                    let mut _response = routes.$name.perform_rate_limited(
+                        global_limit,
+                        buckets_store,
                         use_rate_limits,
                         $(&move || {
                             Ok(
-                                ctx.data.http_client.$method(__route.as_str())
+                                _client.$method(AsStrForStr::as_str(&__route))
                                 $(.json($json))? $(.query($query))?
                             )
                         },)?
                         $(&move || {
-                            let $full_request_match = &ctx.data.http_client;
+                            let $full_request_match = &_client;
                             Ok($full_request)
                         },)?
                         reason,
@@ -227,11 +483,7 @@ macro_rules! routes {
                     })?)?))
                 };
 
-                fut.instrument(info_span!(
-                    stringify!($name),
-                    id = API_CALL_COUNT.fetch_add(1, Ordering::Relaxed),
-                    $(%$param,)*
-                )).await
+                fut.instrument(span).await
             }
         )*}
     }
@@ -276,36 +528,33 @@ routes! {
         request: get("/channels/{}/messages/{}", ch.0, msg.0),
     }
     /// Posts a message to a channel.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the total size of the attached files exceeds
+    /// [`HttpConfig::max_upload_size`].
     route create_message(ch: ChannelId, %params: CreateMessageParams<'a>, files: Vec<CreateMessageFile<'a>>) on ch -> Message {
         let route = route!("/channels/{}/messages", ch.0);
+        let handles = open_upload_files(&files).await?;
         full_request: |r| {
-            let mut form = Form::new();
-            if files.len() == 1 {
-                form = form.part("file", file_to_part(&files[0])?);
-            } else if !files.is_empty() {
-                for (i, f) in files.iter().enumerate() {
-                    form = form.part(format!("file{}", i), file_to_part(f)?);
-                }
-            }
-            form = form.text("payload_json", serde_json::to_string(&params).unexpected()?);
+            let form = upload_form(&files, &handles, r.max_upload_size)?;
+            let form = form.text("payload_json", serde_json::to_string(&params).unexpected()?);
             r.post(route.as_str()).multipart(form)
         },
     }
     /// Adds a reaction to a message.
     route create_reaction(ch: ChannelId, msg: MessageId, emoji: &EmojiRef) on ch {
-        request: put("/channels/{}/messages/{}/reactions/{}/@me", ch.0, msg.0, emoji),
+        request: put("/channels/{}/messages/{}/reactions/{}/@me", ch.0, msg.0, emoji.url_encoded()),
     }
     /// Removes your reaction from a message.
     route delete_own_reaction(ch: ChannelId, msg: MessageId, emoji: &EmojiRef) on ch {
-        request: delete("/channels/{}/messages/{}/reactions/{}/@me", ch.0, msg.0, emoji),
+        request: delete("/channels/{}/messages/{}/reactions/{}/@me", ch.0, msg.0, emoji.url_encoded()),
     }
     /// Deletes another user's reaction from a message.
     route delete_user_reaction(ch: ChannelId, msg: MessageId, emoji: &EmojiRef, user: UserId) on ch {
-        request: delete("/channels/{}/messages/{}/reactions/{}/{}", ch.0, msg.0, emoji, user.0),
+        request: delete("/channels/{}/messages/{}/reactions/{}/{}", ch.0, msg.0, emoji.url_encoded(), user.0),
     }
     /// Gets the users that reacted to a particular message.
     route get_reactions(ch: ChannelId, msg: MessageId, emoji: &EmojiRef, %params: GetReactionsParams<'_>) on ch -> Vec<User> {
-        request: get("/channels/{}/messages/{}/reactions/{}", ch.0, msg.0, emoji).query(&params),
+        request: get("/channels/{}/messages/{}/reactions/{}", ch.0, msg.0, emoji.url_encoded()).query(&params),
     }
     /// Deletes all reactions from a message.
     route delete_all_reactions(ch: ChannelId, msg: MessageId) on ch {
@@ -313,7 +562,7 @@ routes! {
     }
     /// Deletes all reactions with a certain emoji from a message.
     route delete_all_reactions_for_emoji(ch: ChannelId, msg: MessageId, emoji: &EmojiRef) on ch {
-        request: delete("/channels/{}/messages/{}/reactions/{}", ch.0, msg.0, emoji),
+        request: delete("/channels/{}/messages/{}/reactions/{}", ch.0, msg.0, emoji.url_encoded()),
     }
     /// Edits a message.
     route edit_message(ch: ChannelId, msg: MessageId, %params: EditMessageParams<'_>) on ch -> Message {
@@ -375,6 +624,19 @@ routes! {
     route group_dm_remove_recipient(ch: ChannelId, user: UserId) on ch {
         request: delete("/channels/{}/recipients/{}", ch.0, user.0),
     }
+    /// Creates a new thread with an initial message in a forum channel.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the total size of the attached files exceeds
+    /// [`HttpConfig::max_upload_size`].
+    route start_thread_in_forum(ch: ChannelId, %params: StartThreadInForumParams<'a>, files: Vec<CreateMessageFile<'a>>) on ch -> Channel {
+        let route = route!("/channels/{}/threads", ch.0);
+        let handles = open_upload_files(&files).await?;
+        full_request: |r| {
+            let form = upload_form(&files, &handles, r.max_upload_size)?;
+            let form = form.text("payload_json", serde_json::to_string(&params).unexpected()?);
+            r.post(route.as_str()).multipart(form)
+        },
+    }
 
     // Emoji routes
     ////////////////
@@ -389,7 +651,8 @@ routes! {
     }
     /// Creates an emoji in a guild.
     route create_guild_emoji(guild: GuildId, %params: CreateGuildEmojiParams<'_>) on guild -> Emoji {
-        request: post("/guilds/{}/emojis").json(&params),
+        check: params.image.check_emoji_limits();
+        request: post("/guilds/{}/emojis", guild.0).json(&params),
     }
     /// Modifies an emoji in a guild.
     route modify_guild_emoji(guild: GuildId, emoji: EmojiId, %params: ModifyGuildEmojiParams<'_>) on guild -> Emoji {
@@ -400,6 +663,40 @@ routes! {
         request: delete("/guilds/{}/emojis/{}", guild.0, emoji.0),
     }
 
+    // Sticker routes
+    //////////////////
+
+    /// Returns information about a sticker.
+    route get_sticker(sticker: StickerId) -> Sticker {
+        request: get("/stickers/{}", sticker.0),
+    }
+    /// Returns a list of stickers in a guild.
+    route list_guild_stickers(guild: GuildId) on guild -> Vec<Sticker> {
+        request: get("/guilds/{}/stickers", guild.0),
+    }
+    /// Uploads a sticker to a guild.
+    route create_guild_sticker(guild: GuildId, %params: CreateGuildStickerParams<'a>, file: CreateMessageFile<'a>) on guild -> Sticker {
+        check: check_sticker_file(&file);
+        let route = route!("/guilds/{}/stickers", guild.0);
+        let handle = open_upload_file(&file).await?;
+        full_request: |r| {
+            let form = Form::new()
+                .text("name", params.name.to_string())
+                .text("description", params.description.to_string())
+                .text("tags", params.tags.to_string())
+                .part("file", file_to_part(&file, handle.as_ref())?);
+            r.post(route.as_str()).multipart(form)
+        },
+    }
+    /// Modifies a sticker in a guild.
+    route modify_guild_sticker(guild: GuildId, sticker: StickerId, %params: ModifyGuildStickerParams<'_>) on guild -> Sticker {
+        request: patch("/guilds/{}/stickers/{}", guild.0, sticker.0).json(&params),
+    }
+    /// Deletes a sticker from a guild.
+    route delete_guild_sticker(guild: GuildId, sticker: StickerId) on guild {
+        request: delete("/guilds/{}/stickers/{}", guild.0, sticker.0),
+    }
+
     // Guild routes
     ////////////////
 
@@ -407,25 +704,29 @@ routes! {
     route create_guild(,%params: CreateGuildParams<'_>) -> Guild {
         request: post("/guilds").json(&params),
     }
+    /// Returns a guild's settings.
+    route get_guild(guild: GuildId, %params: GetGuildParams<'_>) on guild -> Guild {
+        request: get("/guilds/{}", guild.0).query(&params),
+    }
     /// Modifies a guild's settings.
     route modify_guild(guild: GuildId, %params: ModifyGuildParams<'_>) on guild -> Guild {
-        request: patch("/guilds/{}").json(&params),
+        request: patch("/guilds/{}", guild.0).json(&params),
     }
     /// Deletes a guild the bot owns.
     route delete_guild(guild: GuildId) on guild {
-        request: delete("/guilds/{}"),
+        request: delete("/guilds/{}", guild.0),
     }
     /// Returns a list of channels in a guild.
     route get_guild_channels(guild: GuildId) on guild -> Vec<Channel> {
-        request: get("/guilds/{}/channels"),
+        request: get("/guilds/{}/channels", guild.0),
     }
     /// Creates a channel in a guild.
     route create_guild_channel(guild: GuildId, %params: CreateGuildChannelParams<'_>) on guild -> Channel {
-        request: post("/guilds/{}/channels").json(&params),
+        request: post("/guilds/{}/channels", guild.0).json(&params),
     }
     /// Changes the position of a channel in a guild.
     route modify_guild_channel_position(guild: GuildId, %params: Vec<ModifyGuildChannelPositionParams>) on guild {
-        request: patch("/guilds/{}/channels").json(&params),
+        request: patch("/guilds/{}/channels", guild.0).json(&params),
     }
     /// Gets information about a guild member.
     route get_guild_member(guild: GuildId, member: UserId) on guild -> Member {
@@ -435,6 +736,10 @@ routes! {
     route list_guild_members(guild: GuildId, %params: ListGuildMembersParams<'_>) on guild -> Vec<Member> {
         request: get("/guilds/{}/members", guild.0).query(&params),
     }
+    /// Searches for guild members whose username or nickname starts with a query string.
+    route search_guild_members(guild: GuildId, %params: SearchGuildMembersParams<'_>) on guild -> Vec<Member> {
+        request: get("/guilds/{}/members/search", guild.0).query(&params),
+    }
     /// Adds a member to the guild. Requires an access token for them with the `guilds.join` scope.
     route add_guild_member(guild: GuildId, member: UserId, %params: AddGuildMemberParams<'_>) on guild {
         request: put("/guilds/{}/members/{}", guild.0, member.0).json(&params),
@@ -460,6 +765,10 @@ routes! {
     route remove_guild_member(guild: GuildId, member: UserId) on guild {
         request: delete("/guilds/{}/members/{}", guild.0, member.0),
     }
+    /// Returns a page of a guild's audit log.
+    route get_guild_audit_log(guild: GuildId, %params: GetGuildAuditLogParams<'_>) on guild -> AuditLog {
+        request: get("/guilds/{}/audit-logs", guild.0).query(&params),
+    }
     /// Returns a list of bans in a guild.
     route get_guild_bans(guild: GuildId) on guild -> Vec<GuildBan> {
         request: get("/guilds/{}/bans", guild.0),
@@ -468,7 +777,7 @@ routes! {
     route get_guild_ban(guild: GuildId, member: UserId) on guild -> GuildBan {
         request: get("/guilds/{}/bans/{}", guild.0, member.0),
     }
-    /// CBan a user from a guild.
+    /// Bans a user from a guild.
     route create_guild_ban(guild: GuildId, member: UserId, %params: CreateGuildBanParams<'_>) on guild {
         request: put("/guilds/{}/bans/{}", guild.0, member.0).query(&params),
     }
@@ -486,7 +795,7 @@ routes! {
     }
     /// Changes the hierarchy of roles in a guild.
     route modify_guild_role_position(guild: GuildId, %params: Vec<ModifyGuildRolePositionParams>) on guild {
-        request: patch("/guilds/{}/roles").json(&params),
+        request: patch("/guilds/{}/roles", guild.0).json(&params),
     }
     /// Changes a role in a guild.
     route modify_guild_role(guild: GuildId, role: RoleId, %params: GuildRoleParams<'_>) on guild -> Role {
@@ -518,31 +827,182 @@ routes! {
     // TODO: Delete Guild Integration
     // TODO: Sync Guild Integration
     /// Returns a guild's embed settings.
+    ///
+    /// Discord replaced the guild embed endpoints with the guild widget endpoints, so this calls
+    /// the same endpoint as [`Routes::get_guild_widget_settings`].
     route get_guild_embed(guild: GuildId) on guild -> GuildEmbedSettings {
-        request: get("/guilds/{}/embed", guild.0),
+        request: get("/guilds/{}/widget", guild.0),
     }
     /// Changes a guild's embed settings.
+    ///
+    /// Discord replaced the guild embed endpoints with the guild widget endpoints, so this calls
+    /// the same endpoint as [`Routes::modify_guild_widget`].
     route modify_guild_embed(guild: GuildId, %params: ModifyGuildEmbedParams<'_>) on guild -> GuildEmbedSettings {
-        request: patch("/guilds/{}/embed", guild.0).json(&params),
+        request: patch("/guilds/{}/widget", guild.0).json(&params),
+    }
+    /// Returns a guild's widget settings.
+    route get_guild_widget_settings(guild: GuildId) on guild -> GuildWidgetSettings {
+        request: get("/guilds/{}/widget", guild.0),
+    }
+    /// Changes a guild's widget settings.
+    route modify_guild_widget(guild: GuildId, %params: ModifyGuildWidgetParams<'_>) on guild -> GuildWidgetSettings {
+        request: patch("/guilds/{}/widget", guild.0).json(&params),
+    }
+    /// Returns the public information shown in a guild's widget.
+    route get_guild_widget(guild: GuildId) on guild -> GuildWidget {
+        request: get("/guilds/{}/widget.json", guild.0),
     }
     /// Gets a guild's vanity invite URL, if one exists.
     route get_guild_vanity_url(guild: GuildId) on guild -> GetGuildVanityURL {
         request: get("/guilds/{}/vanity-url", guild.0),
     }
+    /// Returns the welcome screen of a guild.
+    route get_guild_welcome_screen(guild: GuildId) on guild -> WelcomeScreen {
+        request: get("/guilds/{}/welcome-screen", guild.0),
+    }
+    /// Modifies the welcome screen of a guild.
+    route modify_guild_welcome_screen(guild: GuildId, %params: ModifyGuildWelcomeScreenParams<'_>) on guild -> WelcomeScreen {
+        request: patch("/guilds/{}/welcome-screen", guild.0).json(&params),
+    }
     // TODO: Get Guild Widget Image
 
+    // Guild template routes
+    /////////////////////////
+
+    /// Returns a guild template from its code.
+    route get_guild_template(code: &str) -> GuildTemplate {
+        request: get("/guilds/templates/{}", code),
+    }
+    /// Creates a new guild based on a guild template.
+    ///
+    /// Unlike most guild routes, this does not require the current user to be in the guild the
+    /// template was created from.
+    route create_guild_from_template(code: &str, %params: CreateGuildFromTemplateParams<'_>) -> Guild {
+        request: post("/guilds/templates/{}", code).json(&params),
+    }
+    /// Returns the templates of a guild.
+    route get_guild_templates(guild: GuildId) on guild -> Vec<GuildTemplate> {
+        request: get("/guilds/{}/templates", guild.0),
+    }
+    /// Creates a new template from a guild.
+    route create_guild_template(guild: GuildId, %params: CreateGuildTemplateParams<'_>) on guild -> GuildTemplate {
+        request: post("/guilds/{}/templates", guild.0).json(&params),
+    }
+    /// Updates a guild template to match the current state of its guild.
+    route sync_guild_template(guild: GuildId, code: &str) on guild -> GuildTemplate {
+        request: put("/guilds/{}/templates/{}", guild.0, code),
+    }
+    /// Modifies the metadata of a guild template.
+    route modify_guild_template(guild: GuildId, code: &str, %params: ModifyGuildTemplateParams<'_>) on guild -> GuildTemplate {
+        request: patch("/guilds/{}/templates/{}", guild.0, code).json(&params),
+    }
+    /// Deletes a guild template.
+    route delete_guild_template(guild: GuildId, code: &str) on guild -> GuildTemplate {
+        request: delete("/guilds/{}/templates/{}", guild.0, code),
+    }
+
+    // Scheduled event routes
+    //////////////////////////
+
+    /// Returns a list of the scheduled events in a guild.
+    route list_scheduled_events(guild: GuildId, %params: GetScheduledEventParams<'_>) on guild -> Vec<ScheduledEvent> {
+        request: get("/guilds/{}/scheduled-events", guild.0).query(&params),
+    }
+    /// Creates a new scheduled event in a guild.
+    route create_scheduled_event(guild: GuildId, %params: CreateScheduledEventParams<'_>) on guild -> ScheduledEvent {
+        request: post("/guilds/{}/scheduled-events", guild.0).json(&params),
+    }
+    /// Returns a scheduled event in a guild.
+    route get_scheduled_event(guild: GuildId, event: ScheduledEventId, %params: GetScheduledEventParams<'_>) on guild -> ScheduledEvent {
+        request: get("/guilds/{}/scheduled-events/{}", guild.0, event.0).query(&params),
+    }
+    /// Modifies a scheduled event in a guild.
+    route modify_scheduled_event(guild: GuildId, event: ScheduledEventId, %params: ModifyScheduledEventParams<'_>) on guild -> ScheduledEvent {
+        request: patch("/guilds/{}/scheduled-events/{}", guild.0, event.0).json(&params),
+    }
+    /// Deletes a scheduled event in a guild.
+    route delete_scheduled_event(guild: GuildId, event: ScheduledEventId) on guild {
+        request: delete("/guilds/{}/scheduled-events/{}", guild.0, event.0),
+    }
+    /// Returns the users subscribed to a scheduled event.
+    route get_scheduled_event_users(guild: GuildId, event: ScheduledEventId, %params: GetScheduledEventUsersParams<'_>) on guild -> Vec<ScheduledEventUser> {
+        request: get("/guilds/{}/scheduled-events/{}/users", guild.0, event.0).query(&params),
+    }
+
+    // Interaction routes
+    //////////////////////
+
+    /// Responds to an interaction. This must be done within 3 seconds of receiving it.
+    route create_interaction_response(id: InteractionId, %token: &InteractionToken, params: InteractionResponse<'_>) on id using token {
+        request: post("/interactions/{}/{}/callback", id.0, token.as_str()).json(&params),
+    }
+    /// Gets the initial response to an interaction.
+    route get_original_interaction_response(app: ApplicationId, %token: &InteractionToken) on app using token -> Message {
+        request: get("/webhooks/{}/{}/messages/@original", app.0, token.as_str()),
+    }
+    /// Edits the initial response to an interaction.
+    route edit_original_interaction_response(app: ApplicationId, %token: &InteractionToken, params: EditMessageParams<'_>) on app using token -> Message {
+        request: patch("/webhooks/{}/{}/messages/@original", app.0, token.as_str()).json(&params),
+    }
+    /// Deletes the initial response to an interaction.
+    route delete_original_interaction_response(app: ApplicationId, %token: &InteractionToken) on app using token {
+        request: delete("/webhooks/{}/{}/messages/@original", app.0, token.as_str()),
+    }
+    /// Sends a followup message for an interaction.
+    ///
+    /// Fails with [`ErrorKind::InvalidInput`] if the total size of the attached files exceeds
+    /// [`HttpConfig::max_upload_size`].
+    route create_followup_message(app: ApplicationId, %token: &InteractionToken, params: CreateMessageParams<'a>, files: Vec<CreateMessageFile<'a>>) on app using token -> Message {
+        let route = route!("/webhooks/{}/{}", app.0, token.as_str());
+        let handles = open_upload_files(&files).await?;
+        full_request: |r| {
+            let form = upload_form(&files, &handles, r.max_upload_size)?;
+            let form = form.text("payload_json", serde_json::to_string(&params).unexpected()?);
+            r.post(route.as_str()).multipart(form)
+        },
+    }
+    /// Edits a followup message for an interaction.
+    route edit_followup_message(app: ApplicationId, %token: &InteractionToken, msg: MessageId, params: EditMessageParams<'_>) on app using token -> Message {
+        request: patch("/webhooks/{}/{}/messages/{}", app.0, token.as_str(), msg.0).json(&params),
+    }
+    /// Deletes a followup message for an interaction.
+    route delete_followup_message(app: ApplicationId, %token: &InteractionToken, msg: MessageId) on app using token {
+        request: delete("/webhooks/{}/{}/messages/{}", app.0, token.as_str(), msg.0),
+    }
+
     // Invite routes
     /////////////////
 
     /// Returns information relating to a Discord invite.
-    route get_invite(invite: &str) -> Invite {
-        request: get("/invites/{}", invite),
+    route get_invite(invite: &str, %params: GetInviteParams<'_>) -> Invite {
+        request: get("/invites/{}", invite).query(&params),
     }
     /// Deletes a Discord invite.
     route delete_invite(invite: &str) -> Invite {
         request: delete("/invites/{}", invite),
     }
 
+    // Stage instance routes
+    ///////////////////////////
+
+    /// Starts a stage instance in a stage channel.
+    route create_stage_instance(ch: ChannelId, %params: CreateStageInstanceParams<'_>) on ch -> StageInstance {
+        let json_params = CreateStageInstanceJsonParams { channel_id: ch, params: &params };
+        request: post("/stage-instances").json(&json_params),
+    }
+    /// Returns the stage instance in a stage channel.
+    route get_stage_instance(ch: ChannelId) on ch -> StageInstance {
+        request: get("/stage-instances/{}", ch.0),
+    }
+    /// Modifies the stage instance in a stage channel.
+    route modify_stage_instance(ch: ChannelId, %params: ModifyStageInstanceParams<'_>) on ch -> StageInstance {
+        request: patch("/stage-instances/{}", ch.0).json(&params),
+    }
+    /// Ends the stage instance in a stage channel.
+    route delete_stage_instance(ch: ChannelId) on ch {
+        request: delete("/stage-instances/{}", ch.0),
+    }
+
     // User routes
     ///////////////
 
@@ -581,8 +1041,71 @@ routes! {
     // TODO: Webhooks
 }
 
-fn file_to_part(file: &CreateMessageFile) -> Result<Part> {
-    Ok(Part::bytes(file.contents().to_vec())
+fn upload_form(
+    files: &[CreateMessageFile], handles: &[Option<File>], max_upload_size: u64,
+) -> Result<Form> {
+    let total_size: u64 = files.iter().map(|f| f.size()).sum();
+    ensure!(total_size <= max_upload_size, InvalidInput, "Attached files are too large.");
+    let mut form = Form::new();
+    if files.len() == 1 {
+        form = form.part("file", file_to_part(&files[0], handles[0].as_ref())?);
+    } else if !files.is_empty() {
+        for (i, (f, handle)) in files.iter().zip(handles).enumerate() {
+            form = form.part(format!("file{}", i), file_to_part(f, handle.as_ref())?);
+        }
+    }
+    Ok(form)
+}
+
+fn check_sticker_file(file: &CreateMessageFile) -> Result<()> {
+    ensure!(file.size() <= STICKER_FILE_SIZE_LIMIT,
+            InvalidInput, "Sticker files cannot be larger than 512 KiB.");
+    match file.mime_type() {
+        "image/png" | "image/apng" | "image/gif" | "application/json" => Ok(()),
+        _ => bail!(InvalidInput, "Sticker files must be PNG, APNG, GIF or Lottie JSON files."),
+    }
+}
+
+/// Opens the files streamed from disk for an API call, so the file handles can be reused if the
+/// call is retried.
+async fn open_upload_files(files: &[CreateMessageFile<'_>]) -> Result<Vec<Option<File>>> {
+    let mut handles = Vec::with_capacity(files.len());
+    for file in files {
+        handles.push(open_upload_file(file).await?);
+    }
+    Ok(handles)
+}
+async fn open_upload_file(file: &CreateMessageFile<'_>) -> Result<Option<File>> {
+    Ok(match file.path() {
+        Some(path) => {
+            let handle = tokio::fs::File::open(path).await
+                .io_err("Could not open file for upload.")?;
+            Some(handle.into_std().await)
+        }
+        None => None,
+    })
+}
+
+/// Creates the multipart body for a file.
+///
+/// Files streamed from disk are sent with the size recorded when the [`CreateMessageFile`] was
+/// created, which is the size checked against the upload limits, even if the file has grown
+/// since then. Files that have shrunk since then cannot be sent.
+fn file_to_part(file: &CreateMessageFile, handle: Option<&File>) -> Result<Part> {
+    let part = match handle {
+        Some(handle) => {
+            let mut handle = handle.try_clone().io_err("Could not read file for upload.")?;
+            let len = file.size();
+            let current_len = handle.metadata().io_err("Could not read file for upload.")?.len();
+            ensure!(current_len >= len, IoError, "File to upload is smaller than when attached.");
+            handle.seek(SeekFrom::Start(0)).io_err("Could not read file for upload.")?;
+            let reader = tokio::fs::File::from_std(handle).take(len);
+            let stream = FramedRead::new(reader, BytesCodec::new());
+            Part::stream_with_length(Body::wrap_stream(stream), len)
+        }
+        None => Part::bytes(file.contents().to_vec()),
+    };
+    Ok(part
         .mime_str(file.mime_type())
         .expect("`Mime` contains invalid media type?")
         .file_name(file.file_name().to_string()))
@@ -610,4 +1133,11 @@ struct ModifyCurrentUserNickJsonParams<'a> {
 #[derive(Serialize)]
 struct CreateDMJsonParams {
     recipient_id: UserId,
+}
+
+#[derive(Serialize)]
+struct CreateStageInstanceJsonParams<'a, 'b> {
+    channel_id: ChannelId,
+    #[serde(flatten)]
+    params: &'a CreateStageInstanceParams<'b>,
 }
\ No newline at end of file
@@ -10,6 +10,8 @@
 #[macro_use] pub mod http;
 
 pub mod api;
+#[cfg(feature = "blocking")] pub mod blocking;
+#[cfg(feature = "cache")] pub mod cache;
 mod context;
 pub mod gateway;
 mod ws;
@@ -66,6 +66,8 @@ fn allocate_buffer(size: usize) -> Vec<u8> {
 }
 
 const BUFFER_MIN_SIZE: usize = 1024*16;
+/// The first byte of a zlib stream, as sent by Discord.
+const ZLIB_MAGIC: u8 = 0x78;
 struct StreamDecoder {
     decoder: Decompress,
     buffer: Vec<u8>,
@@ -91,6 +93,12 @@ impl StreamDecoder {
         Ok((&buf[(decoder.total_in() - last_total_in) as usize..], output_written))
     }
     fn decode_packet<'a>(&'a mut self, data: &'a [u8]) -> LibResult<&'a [u8]> {
+        // With packet compression, each compressed packet is a complete zlib stream of its own,
+        // so the decoder is reset for every packet rather than sharing one transport context.
+        // Packets that do not start with a zlib header were not compressed.
+        if !self.transport && data.first() != Some(&ZLIB_MAGIC) {
+            return Ok(data)
+        }
         if self.buffer.len() > BUFFER_MIN_SIZE && (self.since_last_large > 10 || !self.transport) {
             self.buffer = allocate_buffer(BUFFER_MIN_SIZE);
         }
@@ -203,3 +211,37 @@ impl WebsocketConnection {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::Compression;
+    use flate2::write::ZlibEncoder;
+    use std::io::Write;
+
+    fn compress(data: &[u8]) -> Vec<u8> {
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+
+    #[test]
+    fn decodes_compressed_packets() {
+        let first = br#"{"op":10,"d":{"heartbeat_interval":41250}}"#;
+        let second = br#"{"op":11}"#;
+
+        // Each packet is a separate zlib stream, so decoding must not depend on earlier packets.
+        let mut decoder = StreamDecoder::new(false);
+        let compressed = compress(first);
+        assert_eq!(compressed[0], ZLIB_MAGIC);
+        assert_eq!(decoder.decode_packet(&compressed).unwrap(), &first[..]);
+        assert_eq!(decoder.decode_packet(&compress(second)).unwrap(), &second[..]);
+    }
+
+    #[test]
+    fn passes_through_uncompressed_packets() {
+        let data = br#"{"op":11}"#;
+        let mut decoder = StreamDecoder::new(false);
+        assert_eq!(decoder.decode_packet(data).unwrap(), &data[..]);
+    }
+}
@@ -16,6 +16,7 @@ use std::error::{Error as StdError};
 use std::fmt;
 use std::future::Future;
 use std::panic::{AssertUnwindSafe, catch_unwind};
+use std::time::Duration;
 use thiserror::*;
 
 pub use std::result::{Result as StdResult};
@@ -91,6 +92,19 @@ pub enum ErrorKind {
     /// Discord returned an error status code.
     #[error("{0} failed with {1} ({2})")]
     RequestFailed(&'static str, HttpStatusCode, DiscordError),
+    /// Discord rate limited an API call, and it could not be retried.
+    ///
+    /// This only occurs if the maximum number of retries for rate limited API calls is exceeded.
+    #[error("{0} was rate limited (retry after {1:?})")]
+    RateLimited(&'static str, Duration),
+    /// A gateway command could not be sent because the command queue of the shard is full.
+    ///
+    /// Commands are queued while the shard is disconnected or has exhausted Discord's rate limit
+    /// for gateway commands, so the command may be accepted again later.
+    ///
+    /// The fields are the shard's index and the total shard count, as in `ShardId`.
+    #[error("Gateway command queue is full for shard #{}/{}", .0 + 1, .1)]
+    GatewayQueueFull(u32, u32),
 }
 
 struct ErrorData {
@@ -180,10 +194,68 @@ impl Error {
     /// Returns `true` if this error originated from Discord.
     pub fn is_discord(&self) ->  bool {
         match self.error_kind() {
-            ErrorKind::DiscordBadResponse(_) | ErrorKind::RequestFailed(_, _, _) => true,
+            ErrorKind::DiscordBadResponse(_) | ErrorKind::RequestFailed(_, _, _) |
+            ErrorKind::RateLimited(_, _) => true,
             _ => false,
         }
     }
+
+    /// Returns the error information returned by Discord, if this error was caused by a failed
+    /// API call.
+    pub fn discord_error(&self) -> Option<&DiscordError> {
+        match self.error_kind() {
+            ErrorKind::RequestFailed(_, _, err) => Some(err),
+            _ => None,
+        }
+    }
+
+    /// Returns the error code returned by Discord, if this error was caused by a failed API call.
+    pub fn discord_error_code(&self) -> Option<DiscordErrorCode> {
+        self.discord_error().map(|x| x.code)
+    }
+
+    /// Returns `true` if this error was caused by an API call that failed with the given error
+    /// code.
+    pub fn is_discord_code(&self, code: DiscordErrorCode) -> bool {
+        self.discord_error_code() == Some(code)
+    }
+
+    /// Returns the HTTP status code returned by Discord, if this error was caused by a failed
+    /// API call.
+    pub fn http_status(&self) -> Option<HttpStatusCode> {
+        match self.error_kind() {
+            ErrorKind::RequestFailed(_, status, _) => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this error was caused by an API call being rate limited.
+    pub fn is_rate_limited(&self) -> bool {
+        self.retry_after().is_some()
+    }
+
+    /// Returns how long to wait before retrying, if this error was caused by an API call being
+    /// rate limited.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self.error_kind() {
+            ErrorKind::RateLimited(_, retry_after) => Some(*retry_after),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if this error was caused by an API call on an object that does not exist.
+    ///
+    /// This checks for both a `404 Not Found` status, and for any of Discord's `Unknown *`
+    /// error codes.
+    pub fn is_not_found(&self) -> bool {
+        if self.http_status() == Some(HttpStatusCode::NOT_FOUND) {
+            return true
+        }
+        match self.discord_error_code() {
+            Some(code) => code.is_unknown_object(),
+            None => false,
+        }
+    }
 }
 impl StdError for Error {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
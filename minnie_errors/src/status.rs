@@ -61,6 +61,15 @@ macro_rules! status_codes {
                 }
             }
 
+            /// Returns whether this status code indicates that an object does not exist,
+            /// such as [`UnknownMessage`](`DiscordErrorCode::UnknownMessage`).
+            pub fn is_unknown_object(self) -> bool {
+                match self.as_i32() {
+                    10000..=10999 => true,
+                    _ => false,
+                }
+            }
+
             /// Returns the message for this status code.
             ///
             /// This may be out of date or inaccurate with the message currently used by Discord.
@@ -0,0 +1,195 @@
+//! Types related to guild audit logs.
+
+use crate::channel::*;
+use crate::guild::*;
+use crate::serde::*;
+use crate::types::*;
+use crate::user::*;
+
+enum_with_unknown! {
+    /// The type of action recorded in an audit log entry.
+    ///
+    /// Action types not known to this library are parsed as
+    /// [`Unknown`](`AuditLogEvent::Unknown`) rather than causing an error.
+    pub enum AuditLogEvent(u16) {
+        /// The guild's settings were changed.
+        GuildUpdate = 1,
+        /// A channel was created.
+        ChannelCreate = 10,
+        /// A channel's settings were changed.
+        ChannelUpdate = 11,
+        /// A channel was deleted.
+        ChannelDelete = 12,
+        /// A permission overwrite was added to a channel.
+        ChannelOverwriteCreate = 13,
+        /// A permission overwrite in a channel was changed.
+        ChannelOverwriteUpdate = 14,
+        /// A permission overwrite was removed from a channel.
+        ChannelOverwriteDelete = 15,
+        /// A member was kicked from the guild.
+        MemberKick = 20,
+        /// Inactive members were pruned from the guild.
+        MemberPrune = 21,
+        /// A user was banned from the guild.
+        MemberBanAdd = 22,
+        /// A user was unbanned from the guild.
+        MemberBanRemove = 23,
+        /// A member's nickname, mute, deafen or timeout was changed.
+        MemberUpdate = 24,
+        /// Roles were added to or removed from a member.
+        MemberRoleUpdate = 25,
+        /// Members were moved to a different voice channel.
+        MemberMove = 26,
+        /// Members were disconnected from a voice channel.
+        MemberDisconnect = 27,
+        /// A bot was added to the guild.
+        BotAdd = 28,
+        /// A role was created.
+        RoleCreate = 30,
+        /// A role was changed.
+        RoleUpdate = 31,
+        /// A role was deleted.
+        RoleDelete = 32,
+        /// An invite was created.
+        InviteCreate = 40,
+        /// An invite was changed.
+        InviteUpdate = 41,
+        /// An invite was deleted.
+        InviteDelete = 42,
+        /// A webhook was created.
+        WebhookCreate = 50,
+        /// A webhook's settings were changed.
+        WebhookUpdate = 51,
+        /// A webhook was deleted.
+        WebhookDelete = 52,
+        /// An emoji was created.
+        EmojiCreate = 60,
+        /// An emoji was renamed.
+        EmojiUpdate = 61,
+        /// An emoji was deleted.
+        EmojiDelete = 62,
+        /// A message sent by another user was deleted.
+        MessageDelete = 72,
+        /// Multiple messages were deleted at once.
+        MessageBulkDelete = 73,
+        /// A message was pinned.
+        MessagePin = 74,
+        /// A message was unpinned.
+        MessageUnpin = 75,
+        /// An integration was added to the guild.
+        IntegrationCreate = 80,
+        /// An integration was changed.
+        IntegrationUpdate = 81,
+        /// An integration was removed from the guild.
+        IntegrationDelete = 82,
+        /// A stage instance was started.
+        StageInstanceCreate = 83,
+        /// A stage instance was changed.
+        StageInstanceUpdate = 84,
+        /// A stage instance was ended.
+        StageInstanceDelete = 85,
+        /// A sticker was created.
+        StickerCreate = 90,
+        /// A sticker was changed.
+        StickerUpdate = 91,
+        /// A sticker was deleted.
+        StickerDelete = 92,
+        /// A scheduled event was created.
+        ScheduledEventCreate = 100,
+        /// A scheduled event was changed.
+        ScheduledEventUpdate = 101,
+        /// A scheduled event was cancelled.
+        ScheduledEventDelete = 102,
+        /// A thread was created.
+        ThreadCreate = 110,
+        /// A thread was changed.
+        ThreadUpdate = 111,
+        /// A thread was deleted.
+        ThreadDelete = 112,
+    }
+}
+
+/// The old or new value of a property changed by an audit logged action.
+///
+/// The type of the value depends on the property that was changed, so this stores the raw JSON
+/// of the value.
+pub type AuditLogChangeValue = RawJson;
+
+/// A property changed by an audit logged action.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct AuditLogChange {
+    /// The name of the property that was changed.
+    pub key: String,
+    /// The value of the property after the change.
+    pub new_value: Option<AuditLogChangeValue>,
+    /// The value of the property before the change.
+    pub old_value: Option<AuditLogChangeValue>,
+}
+
+/// Additional information included with certain audit logged actions.
+///
+/// Which fields are present depends on the action type of the entry.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct AuditLogEntryInfo {
+    /// The number of days of inactivity after which members were pruned.
+    pub delete_member_days: Option<String>,
+    /// The number of members removed by a prune.
+    pub members_removed: Option<String>,
+    /// The channel targeted by the action.
+    pub channel_id: Option<ChannelId>,
+    /// The message targeted by the action.
+    pub message_id: Option<MessageId>,
+    /// The number of entities targeted by the action.
+    pub count: Option<String>,
+    /// The ID of the role or user a permission overwrite was changed for.
+    pub id: Option<Snowflake>,
+    /// The type of the permission overwrite that was changed.
+    #[serde(rename = "type")]
+    pub overwrite_type: Option<String>,
+    /// The name of the role a permission overwrite was changed for.
+    pub role_name: Option<String>,
+}
+
+/// An entry in a guild's audit log.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct AuditLogEntry {
+    /// The ID of the entry.
+    pub id: AuditLogEntryId,
+    /// The ID of the entity targeted by the action.
+    pub target_id: Option<Snowflake>,
+    /// The properties changed by the action.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub changes: Vec<AuditLogChange>,
+    /// The user that performed the action.
+    pub user_id: Option<UserId>,
+    /// The type of the action.
+    pub action_type: AuditLogEvent,
+    /// Additional information about the action.
+    pub options: Option<AuditLogEntryInfo>,
+    /// The reason given for the action.
+    pub reason: Option<String>,
+}
+into_id!(AuditLogEntry, AuditLogEntryId, id);
+
+/// A page of a guild's audit log.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct AuditLog {
+    /// The entries in this page, from newest to oldest.
+    pub audit_log_entries: Vec<AuditLogEntry>,
+    /// The users referenced by the entries.
+    #[serde(default)]
+    pub users: Vec<User>,
+    /// The webhooks referenced by the entries.
+    #[serde(default)]
+    pub webhooks: Vec<Webhook>,
+    /// The integrations referenced by the entries.
+    #[serde(default)]
+    pub integrations: Vec<Integration>,
+}
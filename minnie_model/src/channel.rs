@@ -8,36 +8,40 @@ use crate::user::*;
 use std::fmt;
 use std::time::Duration;
 
-/// The type of an channel.
-#[derive(Serialize_repr, Deserialize_repr)]
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
-#[non_exhaustive]
-#[repr(i32)]
-pub enum ChannelType {
-    /// A normal text channel in a guild.
-    GuildText = 0,
-    /// A direct message channel.
-    Dm = 1,
-    /// A voice channel in a guild.
-    GuildVoice = 2,
-    /// A group DM channel.
-    GroupDm = 3,
-    /// A category in a guild.
-    GuildCategory = 4,
-    /// A news text channel in a guild.
-    GuildNews = 5,
-    /// A store channel in a guild.
-    GuildStore = 6,
-    /// An unrecognized channel type.
-    #[serde(other)]
-    Unknown = i32::max_value(),
+enum_with_unknown! {
+    /// The type of an channel.
+    ///
+    /// Channel types not known to this library are parsed as [`Unknown`](`ChannelType::Unknown`)
+    /// rather than causing an error.
+    pub enum ChannelType(u8) {
+        /// A normal text channel in a guild.
+        GuildText = 0,
+        /// A direct message channel.
+        Dm = 1,
+        /// A voice channel in a guild.
+        GuildVoice = 2,
+        /// A group DM channel.
+        GroupDm = 3,
+        /// A category in a guild.
+        GuildCategory = 4,
+        /// A news text channel in a guild.
+        GuildNews = 5,
+        /// A store channel in a guild.
+        GuildStore = 6,
+        /// A stage channel in a guild.
+        GuildStageVoice = 13,
+        /// A forum channel in a guild, which only contains threads.
+        GuildForum = 15,
+    }
 }
 
 /// The type of id in a permission overwrite.
-#[derive(Serialize, Deserialize, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
-#[serde(rename_all = "lowercase")]
+#[derive(Serialize_repr, Deserialize_repr)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[repr(u8)]
 pub enum PermissionOverwriteType {
-    Role, Member,
+    Role = 0,
+    Member = 1,
 }
 
 /// A permission overwrite in a channel, before the id/type fields are properly parsed out.
@@ -46,7 +50,9 @@ struct RawPermissionOverwrite {
     id: Snowflake,
     #[serde(rename = "type")]
     overwrite_type: PermissionOverwriteType,
+    #[serde(with = "utils::permissions")]
     allow: EnumSet<Permission>,
+    #[serde(with = "utils::permissions")]
     deny: EnumSet<Permission>,
 }
 
@@ -214,8 +220,52 @@ pub struct Channel {
     pub application_id: Option<ApplicationId>,
     pub parent_id: Option<CategoryId>,
     pub last_pin_timestamp: Option<DateTime<Utc>>,
+    /// The tags that can be applied to threads in this (forum) channel.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub available_tags: Vec<ForumTag>,
+    /// The emoji shown in the add reaction button of threads in this (forum) channel.
+    pub default_reaction_emoji: Option<DefaultReaction>,
+    /// The tags applied to this thread in a forum channel.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub applied_tags: Vec<ForumTagId>,
 }
 into_id!(Channel, ChannelId, id);
+impl Channel {
+    /// Returns whether this channel is a guild category.
+    pub fn is_category(&self) -> bool {
+        self.channel_type == ChannelType::GuildCategory
+    }
+}
+
+/// A tag that can be applied to threads in a forum channel.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct ForumTag {
+    /// The ID of this tag.
+    pub id: ForumTagId,
+    /// The name of this tag.
+    pub name: String,
+    /// Whether this tag can only be applied by users with the `Manage Threads` permission.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    pub moderated: bool,
+    /// The ID of the guild's custom emoji for this tag.
+    pub emoji_id: Option<EmojiId>,
+    /// The unicode character of the emoji for this tag.
+    pub emoji_name: Option<String>,
+}
+into_id!(ForumTag, ForumTagId, id);
+
+/// The default emoji shown in the add reaction button of threads in a forum channel.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct DefaultReaction {
+    /// The ID of the guild's custom emoji.
+    pub emoji_id: Option<EmojiId>,
+    /// The unicode character of the emoji.
+    pub emoji_name: Option<String>,
+}
 
 /// The type of user invited to a Discord channel.
 #[derive(Serialize_repr, Deserialize_repr)]
@@ -230,6 +280,38 @@ pub enum InviteTargetUserType {
     Unknown = i32::max_value(),
 }
 
+/// Who can see a stage instance.
+#[derive(Serialize_repr, Deserialize_repr)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[repr(i32)]
+#[non_exhaustive]
+pub enum StagePrivacyLevel {
+    /// The stage instance is visible publicly.
+    Public = 1,
+    /// The stage instance is visible only to guild members.
+    GuildOnly = 2,
+    /// An unknown privacy level was set.
+    #[serde(other)]
+    Unknown = i32::max_value(),
+}
+
+/// A live stage in a stage channel.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct StageInstance {
+    /// The ID of the stage instance.
+    pub id: StageInstanceId,
+    /// The guild the stage channel is in.
+    pub guild_id: GuildId,
+    /// The stage channel the stage instance is in.
+    pub channel_id: ChannelId,
+    /// The topic of the stage instance.
+    pub topic: String,
+    /// Who can see the stage instance.
+    pub privacy_level: StagePrivacyLevel,
+}
+into_id!(StageInstance, StageInstanceId, id);
+
 /// An invite to a channel or guild.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -251,6 +333,10 @@ pub struct Invite {
     pub approximate_presence_count: Option<u32>,
     /// An estimate of the number of members in the guild.
     pub approximate_member_count: Option<u32>,
+    /// When the invite expires, if ever.
+    ///
+    /// Only available when retrieved with `with_expiration` set.
+    pub expires_at: Option<DateTime<Utc>>,
 }
 impl Invite {
     /// Returns a link to this invite.
@@ -292,4 +378,43 @@ impl InviteWithMetadata {
     pub fn link(self) -> String {
         self.invite.link()
     }
-}
\ No newline at end of file
+}
+
+enum_with_unknown! {
+    /// The type of a webhook.
+    pub enum WebhookType(u8) {
+        /// A webhook that messages can be posted to with its token.
+        Incoming = 1,
+        /// A webhook that posts messages from a followed announcement channel.
+        ChannelFollower = 2,
+        /// A webhook used with interactions.
+        Application = 3,
+    }
+}
+
+/// A webhook that posts messages to a channel.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct Webhook {
+    /// The ID of the webhook.
+    pub id: WebhookId,
+    /// The type of the webhook.
+    #[serde(rename = "type")]
+    pub webhook_type: WebhookType,
+    /// The guild the webhook posts to.
+    pub guild_id: Option<GuildId>,
+    /// The channel the webhook posts to.
+    pub channel_id: Option<ChannelId>,
+    /// The user that created the webhook.
+    pub user: Option<User>,
+    /// The default name of the webhook.
+    pub name: Option<String>,
+    /// The default avatar hash of the webhook.
+    pub avatar: Option<String>,
+    /// The token of the webhook, if it is an incoming webhook.
+    pub token: Option<WebhookToken>,
+    /// The application that created the webhook.
+    pub application_id: Option<ApplicationId>,
+}
+into_id!(Webhook, WebhookId, id);
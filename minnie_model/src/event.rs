@@ -3,12 +3,14 @@
 use chrono::{DateTime, Utc};
 use crate::channel::*;
 use crate::guild::*;
+use crate::interaction::*;
 use crate::message::*;
 use crate::serde::*;
 use crate::types::*;
 use crate::user::*;
 use std::fmt::{Formatter, Result as FmtResult};
 use std::str::FromStr;
+use strum::IntoEnumIterator;
 use std::time::SystemTime;
 
 /// A `Channel Create` event.
@@ -131,12 +133,31 @@ pub struct GuildMembersChunkEvent {
     pub guild_id: GuildId,
     /// A partial list of members in the guild.
     pub members: Vec<Member>,
-    /// A list of guilds no presences were found for.
+    /// A list of requested user IDs that were not found in the guild.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
-    pub not_found: Vec<GuildId>,
+    pub not_found: Vec<UserId>,
     /// A partial list of presences in the guild.
+    ///
+    /// Presences that could not be parsed contain only the user ID, and have their `malformed`
+    /// field set to true.
     #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserialize_presences_lenient")]
     pub presences: Option<Vec<Presence>>,
+    /// The index of this chunk in the response to a request.
+    #[serde(default)]
+    pub chunk_index: u32,
+    /// The total number of chunks in the response to a request.
+    #[serde(default)]
+    pub chunk_count: u32,
+    /// The nonce of the request this chunk is a response to.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
+}
+impl GuildMembersChunkEvent {
+    /// Returns whether this is the last chunk sent in response to a request.
+    pub fn is_last_chunk(&self) -> bool {
+        self.chunk_index + 1 >= self.chunk_count
+    }
 }
 
 /// A `Guild Role Create` event.
@@ -169,6 +190,30 @@ pub struct GuildRoleDeleteEvent {
     pub role_id: RoleId,
 }
 
+/// A `Guild Scheduled Event Create` event.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+#[allow(missing_docs)]
+pub struct GuildScheduledEventCreateEvent(pub ScheduledEvent);
+
+/// A `Guild Scheduled Event Update` event.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+#[allow(missing_docs)]
+pub struct GuildScheduledEventUpdateEvent(pub ScheduledEvent);
+
+/// A `Guild Scheduled Event Delete` event.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+#[allow(missing_docs)]
+pub struct GuildScheduledEventDeleteEvent(pub ScheduledEvent);
+
+/// A `Interaction Create` event.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+#[allow(missing_docs)]
+pub struct InteractionCreateEvent(pub Interaction);
+
 /// A `Invite Create` event.
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[non_exhaustive]
@@ -353,6 +398,84 @@ pub struct MessageReactionRemoveEmojiEvent {
 pub(crate) struct MalformedPresenceUpdateEvent {
     pub user: PartialUser,
 }
+impl MalformedPresenceUpdateEvent {
+    pub(crate) fn into_presence(self) -> Presence {
+        Presence {
+            user: self.user,
+            nick: None,
+            roles: Vec::new(),
+            game: None,
+            guild_id: None,
+            status: None,
+            activites: Vec::new(),
+            client_status: None,
+            premium_since: None,
+            malformed: true,
+        }
+    }
+}
+
+/// Parses a single field of a malformed presence, setting `malformed` if it cannot be parsed.
+fn parse_presence_field<T: DeserializeOwned>(
+    value: &JsonValue, name: &str, malformed: &mut bool,
+) -> Option<T> {
+    match value.get(name) {
+        None | Some(JsonValue::Null) => None,
+        Some(field) => match T::deserialize(field) {
+            Ok(field) => Some(field),
+            Err(_) => {
+                *malformed = true;
+                None
+            }
+        },
+    }
+}
+
+/// Parses a presence, salvaging as many fields as possible if it is malformed.
+///
+/// Only the user is required to be well-formed. Any other field that cannot be parsed is left
+/// empty, and the `malformed` field is set if this happens.
+pub(crate) fn parse_presence_lenient(value: &JsonValue) -> serde_json::Result<Presence> {
+    if let Ok(presence) = Presence::deserialize(value) {
+        return Ok(presence)
+    }
+
+    let mut malformed = false;
+    let mut presence = MalformedPresenceUpdateEvent::deserialize(value)?.into_presence();
+    presence.nick = parse_presence_field(value, "nick", &mut malformed);
+    presence.roles = parse_presence_field(value, "roles", &mut malformed).unwrap_or_default();
+    presence.game = parse_presence_field(value, "game", &mut malformed);
+    presence.guild_id = parse_presence_field(value, "guild_id", &mut malformed);
+    presence.status = parse_presence_field(value, "status", &mut malformed);
+    presence.client_status = parse_presence_field(value, "client_status", &mut malformed);
+    presence.premium_since = parse_presence_field(value, "premium_since", &mut malformed);
+    match value.get("activities") {
+        None | Some(JsonValue::Null) => { }
+        Some(JsonValue::Array(activities)) => for activity in activities {
+            match Activity::deserialize(activity) {
+                Ok(activity) => presence.activites.push(activity),
+                Err(_) => malformed = true,
+            }
+        },
+        Some(_) => malformed = true,
+    }
+    presence.malformed = malformed;
+    Ok(presence)
+}
+fn deserialize_presences_lenient<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Vec<Presence>>, D::Error> {
+    match Option::<Vec<JsonValue>>::deserialize(deserializer)? {
+        Some(values) => {
+            let mut presences = Vec::new();
+            for value in &values {
+                presences.push(parse_presence_lenient(value).map_err(D::Error::custom)?);
+            }
+            Ok(Some(presences))
+        }
+        None => Ok(None),
+    }
+}
 
 /// A `Presence Update` event.
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -462,6 +585,10 @@ pub enum GatewayEvent {
     GuildRoleCreate(GuildRoleCreateEvent),
     GuildRoleUpdate(GuildRoleUpdateEvent),
     GuildRoleDelete(GuildRoleDeleteEvent),
+    GuildScheduledEventCreate(GuildScheduledEventCreateEvent),
+    GuildScheduledEventUpdate(GuildScheduledEventUpdateEvent),
+    GuildScheduledEventDelete(GuildScheduledEventDeleteEvent),
+    InteractionCreate(InteractionCreateEvent),
     InviteCreate(InviteCreateEvent),
     InviteDelete(InviteDeleteEvent),
     MessageCreate(MessageCreateEvent),
@@ -508,6 +635,10 @@ pub enum GatewayEventType {
     GuildRoleCreate,
     GuildRoleUpdate,
     GuildRoleDelete,
+    GuildScheduledEventCreate,
+    GuildScheduledEventUpdate,
+    GuildScheduledEventDelete,
+    InteractionCreate,
     InviteCreate,
     InviteDelete,
     MessageCreate,
@@ -531,7 +662,31 @@ pub enum GatewayEventType {
     Unknown(String),
 }
 impl GatewayEventType {
+    /// Returns an iterator over all event types known to the library.
+    pub fn known_events() -> impl Iterator<Item = GatewayEventType> {
+        GatewayEventType::iter().filter(|x| !matches!(x, GatewayEventType::Unknown(_)))
+    }
+
     /// The intent this gateway event uses.
+    ///
+    /// Returns `None` for events that are received regardless of the intents requested, such as
+    /// `Ready` or `Interaction Create`, and for events not known to the library. `Guild Members
+    /// Chunk` events are sent in response to guild member requests, and are not gated behind an
+    /// intent of their own.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use minnie_model::event::GatewayEventType::{self, *};
+    /// for event in GatewayEventType::known_events() {
+    ///     let ungated = matches!(
+    ///         event,
+    ///         Ready | Resumed | UserUpdate | GuildMembersChunk | PresencesReplace |
+    ///         InteractionCreate,
+    ///     );
+    ///     assert_eq!(event.intent().is_none(), ungated, "{}", event);
+    /// }
+    /// ```
     pub fn intent(&self) -> Option<EnumSet<GatewayIntent>> {
         use GatewayEventType::*;
         match self {
@@ -550,7 +705,9 @@ impl GatewayEventType {
                 => Some(GatewayIntent::GuildWebhooks.into()),
             InviteCreate | InviteDelete
                 => Some(GatewayIntent::GuildInvites.into()),
-            VoiceStateUpdate
+            GuildScheduledEventCreate | GuildScheduledEventUpdate | GuildScheduledEventDelete
+                => Some(GatewayIntent::GuildScheduledEvents.into()),
+            VoiceStateUpdate | VoiceServerUpdate
                 => Some(GatewayIntent::GuildVoiceStates.into()),
             PresenceUpdate
                 => Some(GatewayIntent::GuildPresences.into()),
@@ -566,7 +723,9 @@ impl GatewayEventType {
                         GatewayIntent::DirectMessageTyping),
             ChannelCreate
                 => Some(GatewayIntent::Guilds | GatewayIntent::DirectMessages),
-            _ => None,
+            Ready | Resumed | UserUpdate | GuildMembersChunk | PresencesReplace |
+            InteractionCreate | Unknown(_)
+                => None,
         }
     }
 }
@@ -634,6 +793,7 @@ pub enum GatewayIntent {
     DirectMessages = 12,
     DirectMessageReactions = 13,
     DirectMessageTyping = 14,
+    GuildScheduledEvents = 16,
 }
 impl GatewayIntent {
     /// Returns true if a gateway privilege requires special permissions.
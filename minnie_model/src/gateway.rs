@@ -73,6 +73,12 @@ pub struct GuildMembersRequest {
     /// A list of user IDs to request information about.
     #[setters(into)]
     pub user_ids: Option<Vec<UserId>>,
+    /// A nonce returned in the resulting `Guild Members Chunk` events.
+    ///
+    /// This allows the chunks to be matched to the request when several are in flight. Discord
+    /// ignores nonces longer than 32 bytes.
+    #[setters(into)]
+    pub nonce: Option<String>,
 }
 impl GuildMembersRequest {
     /// Creates a request for the given guild ID.
@@ -112,6 +118,16 @@ pub struct ConnectionProperties {
     #[serde(rename = "$device")]
     pub device: String,
 }
+impl ConnectionProperties {
+    /// Creates a new set of connection properties.
+    pub fn new(os: impl ToString, browser: impl ToString, device: impl ToString) -> Self {
+        ConnectionProperties {
+            os: os.to_string(),
+            browser: browser.to_string(),
+            device: device.to_string(),
+        }
+    }
+}
 
 /// The contents of the `Identify` packet.
 #[serde_with::skip_serializing_none]
@@ -203,6 +219,18 @@ impl GatewayOpcode {
             GatewayOpcode::Unknown(val)=> *val,
         }
     }
+
+    /// Returns whether packets with this opcode may be sent by users of the library.
+    ///
+    /// This is `false` for opcodes that are only received from Discord, and for opcodes that are
+    /// used to manage the connection itself, such as `Heartbeat`, `Identify` and `Resume`.
+    pub fn should_send(&self) -> bool {
+        match self {
+            GatewayOpcode::StatusUpdate | GatewayOpcode::VoiceStatusUpdate |
+            GatewayOpcode::RequestGuildMembers | GatewayOpcode::Unknown(_) => true,
+            _ => false,
+        }
+    }
 }
 
 /// The sequence number of an event received from a Discord gateway.
@@ -222,12 +250,12 @@ pub struct GatewayPacketFrame<T: Serialize> {
 /// The frame of a packet sent through the Discord gateway.
 ///
 /// Used by the fallback for malformed `Presence Update` packets.
-#[derive(Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Deserialize, Clone, PartialEq, Debug)]
 struct GatewayPacketInvalidPresenceUpdate<'a> {
     op: i128,
     t: &'a str,
     s: PacketSequenceID,
-    d: MalformedPresenceUpdateEvent,
+    d: JsonValue,
 }
 
 /// A packet received from the Discord gateway.
@@ -240,35 +268,31 @@ pub enum GatewayPacket {
     Hello(PacketHello),
     HeartbeatAck,
     UnexpectedPacket(GatewayOpcode),
-    UnknownOpcode(i128),
+    /// A packet with an unknown opcode, along with the raw JSON of its `d` field if it was
+    /// retained.
+    UnknownOpcode(i128, Option<String>),
 }
 impl GatewayPacket {
+    /// Parses a gateway packet.
+    ///
+    /// If `keep_unknown_payloads` is set, the `d` field of packets with unknown opcodes is
+    /// retained as raw JSON in [`GatewayPacket::UnknownOpcode`].
     pub fn from_json(
-        s: &[u8], is_ignored: impl Fn(&GatewayEventType) -> bool,
+        s: &[u8], is_ignored: impl Fn(&GatewayEventType) -> bool, keep_unknown_payloads: bool,
     ) -> LibResult<GatewayPacket> {
-        let seed = GatewayPacketSeed { is_ignored };
+        let seed = GatewayPacketSeed { is_ignored, keep_unknown_payloads };
         match seed.deserialize(&mut serde_json::Deserializer::from_slice(s)) {
             Ok(v) => Ok(v),
             Err(e) => match serde_json::from_slice::<GatewayPacketInvalidPresenceUpdate>(s) {
                 Ok(GatewayPacketInvalidPresenceUpdate { op, t, s, d })
                     if GatewayOpcode::from_i128(op) == GatewayOpcode::Dispatch &&
                         t == "PRESENCE_UPDATE"
-                => {
-                    let ev = GatewayEvent::PresenceUpdate(
-                        PresenceUpdateEvent(Presence {
-                            user: d.user,
-                            nick: None,
-                            roles: Vec::new(),
-                            game: None,
-                            guild_id: None,
-                            status: None,
-                            activites: Vec::new(),
-                            client_status: None,
-                            premium_since: None,
-                            malformed: true,
-                        }),
-                    );
-                    Ok(GatewayPacket::Dispatch(s, GatewayEventType::PresenceUpdate, Some(ev)))
+                => match parse_presence_lenient(&d) {
+                    Ok(presence) => {
+                        let ev = GatewayEvent::PresenceUpdate(PresenceUpdateEvent(presence));
+                        Ok(GatewayPacket::Dispatch(s, GatewayEventType::PresenceUpdate, Some(ev)))
+                    }
+                    Err(_) => Err(e.into()),
                 },
                 _ => Err(e.into())
             }
@@ -285,18 +309,20 @@ impl GatewayPacket {
             GatewayPacket::Hello(_) => GatewayOpcode::Hello,
             GatewayPacket::HeartbeatAck => GatewayOpcode::HeartbeatAck,
             GatewayPacket::UnexpectedPacket(op) => *op,
-            GatewayPacket::UnknownOpcode(op) => GatewayOpcode::Unknown(*op),
+            GatewayPacket::UnknownOpcode(op, _) => GatewayOpcode::Unknown(*op),
         }
     }
 }
 impl <'de> Deserialize<'de> for GatewayPacket {
     fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error> where D: Deserializer<'de> {
-        (GatewayPacketSeed { is_ignored: |_| false }).deserialize(deserializer)
+        let seed = GatewayPacketSeed { is_ignored: |_| false, keep_unknown_payloads: false };
+        seed.deserialize(deserializer)
     }
 }
 
 struct GatewayPacketSeed<F: Fn(&GatewayEventType) -> bool> {
     is_ignored: F,
+    keep_unknown_payloads: bool,
 }
 impl <'de, F: Fn(&GatewayEventType) -> bool> DeserializeSeed<'de> for GatewayPacketSeed<F> {
     type Value = GatewayPacket;
@@ -306,7 +332,10 @@ impl <'de, F: Fn(&GatewayEventType) -> bool> DeserializeSeed<'de> for GatewayPac
     ) -> StdResult<Self::Value, D::Error> where D: Deserializer<'de> {
         deserializer.deserialize_struct(
             "GatewayPacket", &["op", "s", "t", "d"],
-            GatewayPacketVisitor { is_ignored: self.is_ignored },
+            GatewayPacketVisitor {
+                is_ignored: self.is_ignored,
+                keep_unknown_payloads: self.keep_unknown_payloads,
+            },
         )
     }
 }
@@ -363,6 +392,7 @@ fn deserialize_as<T: DeserializeOwned, E: DeError>(val: String) -> StdResult<T,
 }
 struct GatewayPacketVisitor<F: Fn(&GatewayEventType) -> bool> {
     is_ignored: F,
+    keep_unknown_payloads: bool,
 }
 impl <'de, F: Fn(&GatewayEventType) -> bool> Visitor<'de> for GatewayPacketVisitor<F> {
     type Value = GatewayPacket;
@@ -380,6 +410,7 @@ impl <'de, F: Fn(&GatewayEventType) -> bool> Visitor<'de> for GatewayPacketVisit
         let mut d = None;
         let mut delayed_d = None;
         let mut skipped_d = false;
+        let mut unknown_d = None;
 
         let ignored_pkt = |t: &GatewayEventType| match t {
             GatewayEventType::Unknown(_) => true,
@@ -425,6 +456,10 @@ impl <'de, F: Fn(&GatewayEventType) -> bool> Visitor<'de> for GatewayPacketVisit
                                 d = Some(GatewayPacket::InvalidSession(map.next_value()?)),
                             GatewayOpcode::Hello =>
                                 d = Some(GatewayPacket::Hello(map.next_value()?)),
+                            GatewayOpcode::Unknown(_) if self.keep_unknown_payloads => {
+                                unknown_d = Some(map.next_value::<JsonValue>()?.to_string());
+                                skipped_d = true;
+                            }
                             _ => {
                                 map.next_value::<IgnoredAny>()?;
                                 skipped_d = true;
@@ -475,8 +510,10 @@ impl <'de, F: Fn(&GatewayEventType) -> bool> Visitor<'de> for GatewayPacketVisit
                     GatewayPacket::Hello(deserialize_as(delayed_d)?),
                 GatewayOpcode::HeartbeatAck =>
                     GatewayPacket::HeartbeatAck,
+                GatewayOpcode::Unknown(op) if self.keep_unknown_payloads =>
+                    GatewayPacket::UnknownOpcode(op, Some(delayed_d)),
                 GatewayOpcode::Unknown(op) =>
-                    GatewayPacket::UnknownOpcode(op),
+                    GatewayPacket::UnknownOpcode(op, None),
                 op =>
                     GatewayPacket::UnexpectedPacket(op),
             }
@@ -486,7 +523,7 @@ impl <'de, F: Fn(&GatewayEventType) -> bool> Visitor<'de> for GatewayPacketVisit
                 GatewayOpcode::Heartbeat => GatewayPacket::Heartbeat(s.data.take()),
                 GatewayOpcode::Reconnect => GatewayPacket::Reconnect,
                 GatewayOpcode::HeartbeatAck => GatewayPacket::HeartbeatAck,
-                GatewayOpcode::Unknown(op) => GatewayPacket::UnknownOpcode(op),
+                GatewayOpcode::Unknown(op) => GatewayPacket::UnknownOpcode(op, unknown_d),
                 _ => return Err(A::Error::missing_field("d")),
             }
         })
@@ -2,9 +2,11 @@
 
 use chrono::{DateTime, Utc};
 use crate::channel::*;
+use crate::http::{ImageFormat, cdn_url};
 use crate::serde::*;
 use crate::types::*;
 use crate::user::*;
+use std::cmp::Reverse;
 use std::time::Duration;
 
 /// Represents an unavailable guild.
@@ -97,6 +99,17 @@ pub enum PremiumTier {
     #[serde(other)]
     Unknown = i32::max_value(),
 }
+impl PremiumTier {
+    /// Returns the highest bitrate voice channels in a guild with this tier may use.
+    pub fn max_bitrate(self) -> u32 {
+        match self {
+            PremiumTier::None => 96000,
+            PremiumTier::Tier1 => 128000,
+            PremiumTier::Tier2 => 256000,
+            PremiumTier::Tier3 | PremiumTier::Unknown => 384000,
+        }
+    }
+}
 
 /// A special feature a guild may have.
 #[derive(Serialize, Deserialize, EnumSetType, Ord, PartialOrd, Debug, Hash)]
@@ -132,6 +145,7 @@ pub struct Role {
     pub color: Color,
     pub hoist: bool,
     pub position: u64,
+    #[serde(with = "utils::permissions")]
     pub permissions: EnumSet<Permission>,
     pub managed: bool,
     pub mentionable: bool,
@@ -164,6 +178,29 @@ pub struct Member {
     #[serde(flatten)]
     pub info: MemberInfo,
 }
+impl Member {
+    /// Returns how long ago the member joined the guild.
+    ///
+    /// Returns `None` if the join time is in the future, for example due to clock skew.
+    pub fn joined_duration(&self) -> Option<Duration> {
+        (Utc::now() - self.info.joined_at).to_std().ok()
+    }
+
+    /// Returns whether the member is currently boosting the guild.
+    pub fn is_boosting(&self) -> bool {
+        self.info.premium_since.is_some()
+    }
+
+    /// Returns the member's highest role, given the roles of the guild.
+    ///
+    /// Roles are ordered by position, with ties broken in favour of the role with the lower ID as
+    /// Discord does. Returns `None` if the member has no roles other than @everyone.
+    pub fn highest_role<'a>(&self, roles: &'a [Role]) -> Option<&'a Role> {
+        roles.iter()
+            .filter(|role| self.info.roles.contains(&role.id))
+            .max_by_key(|role| (role.position, Reverse(role.id)))
+    }
+}
 
 /// Information related to a member in a Discord guild, without the `user` field. Used in
 /// message objects generated by certain events.
@@ -206,6 +243,7 @@ pub struct PartialGuild {
     pub name: String,
     pub owner: Option<bool>,
     pub owner_id: Option<UserId>,
+    #[serde(default, with = "utils::permissions_opt")]
     pub permissions: Option<EnumSet<Permission>>,
     pub icon: Option<String>,
     pub splash: Option<String>,
@@ -214,12 +252,40 @@ pub struct PartialGuild {
     pub vanity_url_code: Option<String>,
     pub description: Option<String>,
     pub banner: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approximate_member_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approximate_presence_count: Option<u64>,
 }
 impl PartialGuild {
     /// Gets the @everyone role for this guild.
     pub fn everyone_role(self) -> RoleId {
         self.id.everyone_role()
     }
+
+    /// Returns the URL of this guild's icon, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn icon_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.icon.as_ref()?;
+        Some(cdn_url("icons", (self.id.0).0, hash, format, size))
+    }
+
+    /// Returns the URL of this guild's banner, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn banner_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.banner.as_ref()?;
+        Some(cdn_url("banners", (self.id.0).0, hash, format, size))
+    }
+
+    /// Returns the URL of this guild's invite splash, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn splash_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.splash.as_ref()?;
+        Some(cdn_url("splashes", (self.id.0).0, hash, format, size))
+    }
 }
 into_id!(PartialGuild, GuildId, id);
 
@@ -243,7 +309,7 @@ pub struct Guild {
     #[serde(default, skip_serializing_if = "utils::if_false")]
     pub owner: bool,
     pub owner_id: UserId,
-    #[serde(default, skip_serializing_if = "EnumSet::is_empty")]
+    #[serde(default, skip_serializing_if = "EnumSet::is_empty", with = "utils::permissions")]
     pub permissions: EnumSet<Permission>,
     pub region: String,
     pub afk_channel_id: Option<ChannelId>,
@@ -296,12 +362,121 @@ pub struct Guild {
     pub premium_subscription_count: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub preferred_locale: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approximate_member_count: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approximate_presence_count: Option<u64>,
 }
 impl Guild {
     /// Gets the @everyone role for this guild.
     pub fn everyone_role(self) -> RoleId {
         self.id.everyone_role()
     }
+
+    /// Returns the URL of this guild's icon, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn icon_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.icon.as_ref()?;
+        Some(cdn_url("icons", (self.id.0).0, hash, format, size))
+    }
+
+    /// Returns the URL of this guild's banner, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn banner_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.banner.as_ref()?;
+        Some(cdn_url("banners", (self.id.0).0, hash, format, size))
+    }
+
+    /// Returns the URL of this guild's invite splash, if it has one.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn splash_url(&self, format: ImageFormat, size: Option<u32>) -> Option<String> {
+        let hash = self.splash.as_ref()?;
+        Some(cdn_url("splashes", (self.id.0).0, hash, format, size))
+    }
+
+    /// Returns the voice states of the users connected to a voice channel in this guild.
+    ///
+    /// This is only populated for guilds received in `GUILD_CREATE` events, or guilds taken from
+    /// the cache.
+    pub fn voice_states_in(&self, channel: impl Into<ChannelId>) -> Vec<&VoiceState> {
+        let channel = channel.into();
+        self.voice_states.iter().filter(|x| x.channel_id == Some(channel)).collect()
+    }
+
+    /// Returns the position of a member's highest role, or 0 if they only have @everyone.
+    fn top_position(&self, member: &Member) -> u64 {
+        member.highest_role(&self.roles).map_or(0, |role| role.position)
+    }
+
+    /// Returns whether a member is allowed to moderate another member according to Discord's
+    /// role hierarchy, for example by kicking, banning or changing their roles.
+    ///
+    /// The owner of the guild can act on any other member, and no one can act on the owner.
+    /// Otherwise, the actor's highest role must be positioned strictly above the target's. This
+    /// does not check whether the actor has the permissions required for the action.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie_model::guild::*;
+    /// # use serde_json::json;
+    /// let role = |id: &str, position: u64| json!({
+    ///     "id": id, "name": "role", "color": 0, "hoist": false, "position": position,
+    ///     "permissions": "0", "managed": false, "mentionable": false,
+    /// });
+    /// let guild: Guild = serde_json::from_value(json!({
+    ///     "id": "1", "name": "guild", "owner_id": "10", "region": "us-west", "afk_timeout": 300,
+    ///     "verification_level": 0, "default_message_notifications": 0,
+    ///     "explicit_content_filter": 0, "mfa_level": 0, "features": [],
+    ///     "system_channel_flags": 0, "emojis": [],
+    ///     "roles": [role("1", 0), role("2", 1), role("3", 2), role("4", 2)],
+    /// })).unwrap();
+    /// let member = |id: &str, roles: &[&str]| -> Member {
+    ///     serde_json::from_value(json!({
+    ///         "user": { "id": id, "username": "user", "discriminator": "0001", "avatar": null },
+    ///         "roles": roles, "joined_at": "2020-01-01T00:00:00Z", "deaf": false, "mute": false,
+    ///     })).unwrap()
+    /// };
+    /// let owner = member("10", &[]);
+    /// let moderator = member("11", &["3"]);
+    /// let other_moderator = member("12", &["4"]);
+    /// let user = member("13", &["2"]);
+    ///
+    /// // The owner can act on anyone, and no one can act on the owner.
+    /// assert!(guild.can_act_on(&owner, &moderator));
+    /// assert!(!guild.can_act_on(&moderator, &owner));
+    ///
+    /// // Roles at the same position do not outrank each other.
+    /// assert!(guild.can_act_on(&moderator, &user));
+    /// assert!(!guild.can_act_on(&user, &moderator));
+    /// assert!(!guild.can_act_on(&moderator, &other_moderator));
+    ///
+    /// // Only roles below the actor's highest role can be managed.
+    /// assert!(guild.can_manage_role(&moderator, &guild.roles[1]));
+    /// assert!(!guild.can_manage_role(&moderator, &guild.roles[3]));
+    /// assert!(guild.can_manage_role(&owner, &guild.roles[3]));
+    /// ```
+    pub fn can_act_on(&self, actor: &Member, target: &Member) -> bool {
+        if target.user.id == self.owner_id {
+            false
+        } else if actor.user.id == self.owner_id {
+            true
+        } else {
+            self.top_position(actor) > self.top_position(target)
+        }
+    }
+
+    /// Returns whether a member is allowed to manage a role according to Discord's role
+    /// hierarchy, for example by editing it or granting it to other members.
+    ///
+    /// The owner of the guild can manage any role. Otherwise, the member's highest role must be
+    /// positioned strictly above the role. See [`Guild::can_act_on`] for an example.
+    pub fn can_manage_role(&self, actor: &Member, role: &Role) -> bool {
+        actor.user.id == self.owner_id || self.top_position(actor) > role.position
+    }
 }
 into_id!(Guild, GuildId, id);
 
@@ -331,4 +506,316 @@ pub struct VoiceRegion {
 pub struct GuildEmbedSettings {
     pub enabled: bool,
     pub channel_id: Option<ChannelId>,
-}
\ No newline at end of file
+}
+
+/// Information relating to a guild's widget settings.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct GuildWidgetSettings {
+    pub enabled: bool,
+    pub channel_id: Option<ChannelId>,
+}
+
+/// A channel shown in a guild's widget.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct GuildWidgetChannel {
+    pub id: ChannelId,
+    pub name: String,
+    pub position: i32,
+}
+
+/// A member shown in a guild's widget.
+///
+/// The IDs of members are anonymized, and are only unique within a single widget response.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct GuildWidgetMember {
+    pub id: String,
+    pub username: String,
+    pub discriminator: Discriminator,
+    pub avatar: Option<String>,
+    pub status: UserStatus,
+    pub avatar_url: Option<String>,
+}
+
+/// The public information of a guild, as shown in its widget.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct GuildWidget {
+    pub id: GuildId,
+    pub name: String,
+    pub instant_invite: Option<String>,
+    #[serde(default)]
+    pub channels: Vec<GuildWidgetChannel>,
+    #[serde(default)]
+    pub members: Vec<GuildWidgetMember>,
+    pub presence_count: u32,
+}
+into_id!(GuildWidget, GuildId, id);
+
+/// A channel shown in a guild's welcome screen.
+///
+/// Custom emoji are represented by `emoji_id` together with their name in `emoji_name`, while
+/// unicode emoji only set `emoji_name`. Use [`emoji`](`WelcomeScreenChannel::emoji`) to read the
+/// emoji as an [`EmojiRef`]:
+///
+/// ```rust
+/// # use minnie_model::guild::WelcomeScreenChannel;
+/// # use minnie_model::types::*;
+/// let custom: WelcomeScreenChannel = serde_json::from_str(r#"{
+///     "channel_id": "1", "description": "Rules", "emoji_id": "2", "emoji_name": "rules"
+/// }"#).unwrap();
+/// assert_eq!(custom.emoji(), Some(EmojiRef::custom("rules", EmojiId(Snowflake(2)))));
+///
+/// let unicode: WelcomeScreenChannel = serde_json::from_str(r#"{
+///     "channel_id": "1", "description": "Chat", "emoji_id": null, "emoji_name": "💬"
+/// }"#).unwrap();
+/// assert_eq!(unicode.emoji(), Some(EmojiRef::from_unicode("💬")));
+/// ```
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct WelcomeScreenChannel {
+    /// The ID of the channel.
+    #[setters(into)]
+    pub channel_id: ChannelId,
+    /// The description shown for the channel.
+    #[setters(into)]
+    pub description: String,
+    /// The ID of the guild's custom emoji shown for the channel.
+    #[setters(into)]
+    pub emoji_id: Option<EmojiId>,
+    /// The name of the custom emoji, or the unicode character of the emoji shown for the channel.
+    #[setters(into)]
+    pub emoji_name: Option<String>,
+}
+impl WelcomeScreenChannel {
+    /// Creates a new welcome screen channel with no emoji.
+    pub fn new(channel_id: impl Into<ChannelId>, description: impl Into<String>) -> Self {
+        WelcomeScreenChannel {
+            channel_id: channel_id.into(), description: description.into(),
+            emoji_id: None, emoji_name: None,
+        }
+    }
+
+    /// Returns the emoji shown for the channel, if any.
+    pub fn emoji(&self) -> Option<EmojiRef> {
+        match (self.emoji_id, &self.emoji_name) {
+            (Some(id), name) => Some(EmojiRef::Custom(name.clone().map(Into::into), id)),
+            (None, Some(name)) => Some(EmojiRef::from_unicode(name)),
+            (None, None) => None,
+        }
+    }
+
+    /// Sets the emoji shown for the channel.
+    pub fn with_emoji(mut self, emoji: EmojiRef) -> Self {
+        match emoji {
+            EmojiRef::Builtin(name) => {
+                self.emoji_id = None;
+                self.emoji_name = Some(name.into_owned());
+            }
+            EmojiRef::Custom(name, id) => {
+                self.emoji_id = Some(id);
+                self.emoji_name = name.map(|x| x.into_owned());
+            }
+        }
+        self
+    }
+}
+
+/// The welcome screen shown to new members of a community guild.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct WelcomeScreen {
+    /// The description of the guild shown in the welcome screen.
+    pub description: Option<String>,
+    /// The channels shown in the welcome screen.
+    #[serde(default)]
+    pub welcome_channels: Vec<WelcomeScreenChannel>,
+}
+
+/// Who can see a scheduled event.
+#[derive(Serialize_repr, Deserialize_repr)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[repr(i32)]
+#[non_exhaustive]
+pub enum ScheduledEventPrivacyLevel {
+    /// The scheduled event is visible only to guild members.
+    GuildOnly = 2,
+    /// An unknown privacy level was set.
+    #[serde(other)]
+    Unknown = i32::max_value(),
+}
+
+/// The status of a scheduled event.
+#[derive(Serialize_repr, Deserialize_repr)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[repr(i32)]
+#[non_exhaustive]
+pub enum ScheduledEventStatus {
+    /// The scheduled event has not started yet.
+    Scheduled = 1,
+    /// The scheduled event is currently happening.
+    Active = 2,
+    /// The scheduled event has ended.
+    Completed = 3,
+    /// The scheduled event was canceled before it started.
+    Canceled = 4,
+    /// An unknown status was set.
+    #[serde(other)]
+    Unknown = i32::max_value(),
+}
+
+/// Where a scheduled event takes place.
+#[derive(Serialize_repr, Deserialize_repr)]
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[repr(i32)]
+#[non_exhaustive]
+pub enum ScheduledEventEntityType {
+    /// The scheduled event takes place in a stage channel.
+    StageInstance = 1,
+    /// The scheduled event takes place in a voice channel.
+    Voice = 2,
+    /// The scheduled event takes place outside of Discord.
+    External = 3,
+    /// An unknown entity type was set.
+    #[serde(other)]
+    Unknown = i32::max_value(),
+}
+
+/// Additional information about where a scheduled event takes place.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[non_exhaustive]
+pub struct ScheduledEventEntityMetadata {
+    /// The location of an external event.
+    pub location: Option<String>,
+}
+impl ScheduledEventEntityMetadata {
+    /// Creates metadata for an external event at the given location.
+    pub fn location(location: impl Into<String>) -> Self {
+        ScheduledEventEntityMetadata { location: Some(location.into()) }
+    }
+}
+
+/// An event scheduled in a guild.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct ScheduledEvent {
+    /// The ID of the scheduled event.
+    pub id: ScheduledEventId,
+    /// The guild the scheduled event belongs to.
+    pub guild_id: GuildId,
+    /// The channel the scheduled event takes place in, if any.
+    pub channel_id: Option<ChannelId>,
+    /// The ID of the user that created the scheduled event.
+    pub creator_id: Option<UserId>,
+    /// The name of the scheduled event.
+    pub name: String,
+    /// The description of the scheduled event.
+    pub description: Option<String>,
+    /// When the scheduled event starts.
+    pub scheduled_start_time: DateTime<Utc>,
+    /// When the scheduled event ends. This is always present for external events.
+    pub scheduled_end_time: Option<DateTime<Utc>>,
+    /// Who can see the scheduled event.
+    pub privacy_level: ScheduledEventPrivacyLevel,
+    /// The status of the scheduled event.
+    pub status: ScheduledEventStatus,
+    /// Where the scheduled event takes place.
+    pub entity_type: ScheduledEventEntityType,
+    /// The ID of the entity associated with the scheduled event.
+    pub entity_id: Option<Snowflake>,
+    /// Additional information about where the scheduled event takes place.
+    pub entity_metadata: Option<ScheduledEventEntityMetadata>,
+    /// The user that created the scheduled event.
+    pub creator: Option<User>,
+    /// The number of users subscribed to the scheduled event.
+    pub user_count: Option<u32>,
+}
+into_id!(ScheduledEvent, ScheduledEventId, id);
+
+/// A user subscribed to a scheduled event.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct ScheduledEventUser {
+    /// The ID of the scheduled event.
+    #[serde(rename = "guild_scheduled_event_id")]
+    pub scheduled_event_id: ScheduledEventId,
+    /// The subscribed user.
+    pub user: User,
+    /// The subscribed user's guild member information, if requested.
+    pub member: Option<MemberInfo>,
+}
+
+/// The account an integration is connected to.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct IntegrationAccount {
+    /// The ID of the account.
+    pub id: String,
+    /// The name of the account.
+    pub name: String,
+}
+
+/// An integration of a guild with an external service, such as Twitch or YouTube.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct Integration {
+    /// The ID of the integration.
+    pub id: IntegrationId,
+    /// The name of the integration.
+    pub name: String,
+    /// The type of the integration, such as `twitch`, `youtube` or `discord`.
+    #[serde(rename = "type")]
+    pub integration_type: String,
+    /// Whether the integration is enabled.
+    pub enabled: Option<bool>,
+    /// The account the integration is connected to.
+    pub account: Option<IntegrationAccount>,
+}
+into_id!(Integration, IntegrationId, id);
+
+/// A snapshot of a guild's settings, roles and channels stored in a guild template.
+///
+/// This is a partial guild object with the IDs of roles and channels replaced by indexes local to
+/// the template, so this stores the raw JSON of the snapshot.
+pub type TemplateSourceGuild = RawJson;
+
+/// A template that can be used to create new guilds with the structure of an existing guild.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct GuildTemplate {
+    /// The code of the template, used in `discord.new` links.
+    pub code: String,
+    /// The name of the template.
+    pub name: String,
+    /// The description of the template.
+    pub description: Option<String>,
+    /// The number of times the template has been used to create a guild.
+    pub usage_count: u32,
+    /// The ID of the user that created the template.
+    pub creator_id: UserId,
+    /// The user that created the template.
+    pub creator: User,
+    /// When the template was created.
+    pub created_at: DateTime<Utc>,
+    /// When the template was last synced with its source guild.
+    pub updated_at: DateTime<Utc>,
+    /// The ID of the guild the template was created from.
+    pub source_guild_id: GuildId,
+    /// A snapshot of the source guild as of the last sync.
+    pub serialized_source_guild: TemplateSourceGuild,
+    /// Whether the source guild has changed since the last sync.
+    pub is_dirty: Option<bool>,
+}
@@ -1,3 +1,5 @@
+use chrono::{DateTime, Utc};
+use crate::audit_log::*;
 use crate::channel::*;
 use crate::guild::*;
 use crate::message::*;
@@ -7,7 +9,7 @@ use minnie_errors::*;
 use std::borrow::Cow;
 use std::fmt;
 use std::marker::PhantomData;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 
 /// The packet send to indicate that a call has been rate limited.
@@ -16,9 +18,12 @@ pub struct RateLimited {
     /// The message of the rate limit.
     pub message: String,
     /// How long to retry after.
-    #[serde(with = "utils::duration_millis")]
+    ///
+    /// This may be fractional, and is sent in seconds.
+    #[serde(with = "utils::duration_secs_f64")]
     pub retry_after: Duration,
     /// Whether the rate limit hit was global.
+    #[serde(default)]
     pub global: bool,
 }
 
@@ -30,7 +35,7 @@ pub enum ImageFormat {
     Jpeg,
     /// A png image.
     Png,
-    /// A webp video.
+    /// A webp image.
     WebP,
     /// A gif image.
     Gif,
@@ -56,8 +61,58 @@ impl ImageFormat {
             _ => None,
         }
     }
+
+    /// Returns the file extension used for this image format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            ImageFormat::Jpeg => "jpg",
+            ImageFormat::Png => "png",
+            ImageFormat::WebP => "webp",
+            ImageFormat::Gif => "gif",
+        }
+    }
+}
+
+/// The base URL of Discord's CDN.
+pub const CDN_URL: &str = "https://cdn.discordapp.com";
+
+/// Snaps an image size to a power of two supported by Discord's CDN.
+fn snap_cdn_size(size: u32) -> u32 {
+    size.max(16).min(4096).next_power_of_two()
+}
+
+/// Builds the URL of one of Discord's default avatars.
+pub(crate) fn default_avatar_url(discriminator: u16, size: Option<u32>) -> String {
+    let mut url = format!("{}/embed/avatars/{}.png", CDN_URL, discriminator % 5);
+    if let Some(size) = size {
+        url.push_str(&format!("?size={}", snap_cdn_size(size)));
+    }
+    url
+}
+
+/// Builds an URL for an image hash stored on Discord's CDN.
+///
+/// Animated images have hashes beginning with `a_`. If a GIF is requested for an image that
+/// is not animated, a PNG is returned instead.
+pub(crate) fn cdn_url(
+    path: &str, id: u64, hash: &str, format: ImageFormat, size: Option<u32>,
+) -> String {
+    let format = match format {
+        ImageFormat::Gif if !hash.starts_with("a_") => ImageFormat::Png,
+        format => format,
+    };
+    let mut url = format!("{}/{}/{}/{}.{}", CDN_URL, path, id, hash, format.extension());
+    if let Some(size) = size {
+        url.push_str(&format!("?size={}", snap_cdn_size(size)));
+    }
+    url
 }
 
+/// The maximum size of an image uploaded as an emoji.
+pub const EMOJI_SIZE_LIMIT: usize = 256 * 1024;
+/// The maximum size of an image uploaded as an avatar.
+pub const AVATAR_SIZE_LIMIT: usize = 10 * 1024 * 1024;
+
 /// Image data sent to Discord for operations like creating emoji or setting avatars.
 #[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 pub struct ImageData<'a> {
@@ -67,6 +122,17 @@ pub struct ImageData<'a> {
 }
 impl <'a> ImageData<'a> {
     /// Creates image data from a byte array.
+    ///
+    /// The format of the image is detected from its contents. PNG, JPEG, GIF and WebP images
+    /// are supported.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use minnie_model::http::*;
+    /// let webp = ImageData::from_data(b"RIFF\x24\0\0\0WEBPVP8 ").unwrap();
+    /// assert_eq!(webp.format(), ImageFormat::WebP);
+    /// ```
     pub fn from_data(data: impl AsRef<[u8]>) -> Result<Self> {
         Self::from_data_0(data.as_ref())
     }
@@ -83,6 +149,10 @@ impl <'a> ImageData<'a> {
                 return Ok(Self::from_data_with_format_0(*format, data))
             }
         }
+        // WebP images are RIFF containers, with the file size between the two magic numbers.
+        if data.len() >= 12 && data.starts_with(b"RIFF") && &data[8..12] == b"WEBP" {
+            return Ok(Self::from_data_with_format_0(ImageFormat::WebP, data))
+        }
         bail!(InvalidInput, "Could not detect format of given image data.")
     }
 
@@ -154,6 +224,30 @@ impl <'a> ImageData<'a> {
     pub fn data(&self) -> Vec<u8> {
         base64::decode(self.base64_data()).expect("Invalid base64 data!")
     }
+
+    /// Returns the length of the decoded data of this image, without decoding it.
+    pub fn data_len(&self) -> usize {
+        let data = self.base64_data().trim_end_matches('=');
+        data.len() / 4 * 3 + match data.len() % 4 {
+            2 => 1,
+            3 => 2,
+            _ => 0,
+        }
+    }
+
+    /// Checks that this image is small enough to be uploaded as an emoji.
+    pub fn check_emoji_limits(&self) -> Result<()> {
+        ensure!(self.data_len() <= EMOJI_SIZE_LIMIT,
+                InvalidInput, "Emoji images cannot be larger than 256 KiB.");
+        Ok(())
+    }
+
+    /// Checks that this image is small enough to be uploaded as an avatar or guild icon.
+    pub fn check_avatar_limits(&self) -> Result<()> {
+        ensure!(self.data_len() <= AVATAR_SIZE_LIMIT,
+                InvalidInput, "Avatar images cannot be larger than 10 MiB.");
+        Ok(())
+    }
 }
 impl <'a> fmt::Display for ImageData<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -205,6 +299,12 @@ pub struct SessionStartLimit {
     /// The amount of time after which the limit resets.
     #[serde(with = "utils::duration_millis")]
     pub reset_after: Duration,
+    /// The number of shards that may identify at the same time.
+    ///
+    /// Shard `n` belongs to the rate limit bucket `n % max_concurrency`, and each bucket may
+    /// identify once every 5 seconds.
+    #[serde(default = "utils::one")]
+    pub max_concurrency: u32,
 }
 
 /// The return value of the `Get Gateway Bot` endpoint.
@@ -227,11 +327,15 @@ pub struct GetGatewayBot {
 #[non_exhaustive]
 pub struct ModifyChannelParams<'a> {
     /// The channel's name.
+    ///
+    /// Currently limited to 1-100 characters.
     #[setters(into)]
     pub name: Option<Cow<'a, str>>,
     /// The position of this channel within its category.
     pub position: Option<u32>,
     /// This channel's topic.
+    ///
+    /// Currently limited to 0-1024 characters.
     #[setters(into)]
     pub topic: Option<Cow<'a, str>>,
     /// Whether this channel should be considered NSFW.
@@ -243,9 +347,11 @@ pub struct ModifyChannelParams<'a> {
     pub rate_limit_per_user: Option<u32>,
     /// The bitrate of this (voice) channel.
     ///
-    /// Currently ranges from 8000 to 96000, and up to 128000 for VIP servers.
+    /// Currently ranges from 8000 to 96000, and up to 384000 for boosted servers.
     pub bitrate: Option<u32>,
-    /// The user limit of this (voice) channel.
+    /// The user limit of this (voice) channel. A value of zero represents no limit.
+    ///
+    /// Currently ranges from 0-99 for voice channels, and 0-10000 for stage channels.
     pub user_limit: Option<u32>,
     /// The permission overwrites for this channel.
     #[setters(into)]
@@ -256,6 +362,68 @@ pub struct ModifyChannelParams<'a> {
     pub parent_id: Option<Option<ChannelId>>,
 }
 new_from_default!(ModifyChannelParams);
+impl <'a> ModifyChannelParams<'a> {
+    /// Checks the parameters against Discord's documented limits.
+    ///
+    /// As the limits for the user limit and bitrate depend on the type of channel and the guild,
+    /// they are only checked against the largest values Discord allows.
+    pub fn validate(&self) -> Result<()> {
+        if let Some(name) = &self.name {
+            let len = name.chars().count();
+            ensure!((1..=100).contains(&len),
+                    InvalidInput, "Channel names must be 1-100 characters long.");
+        }
+        if let Some(topic) = &self.topic {
+            ensure!(topic.chars().count() <= 1024,
+                    InvalidInput, "Channel topics cannot be longer than 1024 characters.");
+        }
+        if let Some(rate_limit) = self.rate_limit_per_user {
+            ensure!(rate_limit <= 21600,
+                    InvalidInput, "The slowmode rate limit cannot be longer than 21600 seconds.");
+        }
+        if let Some(bitrate) = self.bitrate {
+            ensure!((8000..=384000).contains(&bitrate),
+                    InvalidInput, "Channel bitrates must be between 8000 and 384000.");
+        }
+        if let Some(user_limit) = self.user_limit {
+            ensure!(user_limit <= 10000,
+                    InvalidInput, "Channel user limits cannot be larger than 10000.");
+        }
+        Ok(())
+    }
+
+    /// Checks the parameters against Discord's documented limits, including the bitrate limit
+    /// of a guild with the given premium tier.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use minnie_model::guild::PremiumTier;
+    /// # use minnie_model::http::ModifyChannelParams;
+    /// let params = ModifyChannelParams::new().bitrate(128000);
+    /// assert!(params.validate_for_tier(PremiumTier::Tier1).is_ok());
+    /// assert!(params.validate_for_tier(PremiumTier::None).is_err());
+    /// ```
+    pub fn validate_for_tier(&self, tier: PremiumTier) -> Result<()> {
+        self.validate()?;
+        if let Some(bitrate) = self.bitrate {
+            if bitrate > tier.max_bitrate() {
+                bail!(InvalidInput, match tier {
+                    PremiumTier::None => "Channel bitrates cannot be higher than 96000 in guilds \
+                                          without boosts.",
+                    PremiumTier::Tier1 => "Channel bitrates cannot be higher than 128000 in tier \
+                                           1 guilds.",
+                    PremiumTier::Tier2 => "Channel bitrates cannot be higher than 256000 in tier \
+                                           2 guilds.",
+                    PremiumTier::Tier3 => "Channel bitrates cannot be higher than 384000 in tier \
+                                           3 guilds.",
+                    PremiumTier::Unknown => "Channel bitrates cannot be higher than 384000.",
+                });
+            }
+        }
+        Ok(())
+    }
+}
 
 /// The parameters of the `Get Channel Messages` endpoint.
 #[serde_with::skip_serializing_none]
@@ -305,10 +473,21 @@ pub struct CreateMessageParams<'a> {
     #[serde(default, skip_serializing_if = "utils::if_false")]
     pub tts: bool,
     /// The embed to attach to the post.
+    ///
+    /// This is kept for compatibility. If `embeds` is also set, this embed is sent before them.
     #[setters(into)]
     pub embed: Option<Embed<'a>>,
+    /// The embeds to attach to the post.
+    pub embeds: Option<Vec<Embed<'a>>>,
+    /// The components to attach to the post.
+    pub components: Option<Vec<Component>>,
+    /// The stickers to attach to the post.
+    pub sticker_ids: Option<Vec<StickerId>>,
     /// The types of mentions allowed in the post.
     pub allowed_mentions: Option<AllowedMentions>,
+    /// The message this post is replying to.
+    #[setters(into)]
+    pub message_reference: Option<MessageReference>,
 }
 new_from_default!(CreateMessageParams);
 
@@ -335,16 +514,51 @@ pub enum MentionType {
 pub struct AllowedMentions {
     /// A list of mention types allowed in the message.
     ///
-    /// Mutually exclusive with `roles` and `users`.
+    /// [`MentionType::Roles`] cannot be combined with `roles`, and [`MentionType::Users`] cannot
+    /// be combined with `users`.
     pub parse: Option<EnumSet<MentionType>>,
     /// A list of roles mentions allowed in the message.
     ///
-    /// Mutually exclusive with `parse`.
+    /// Cannot be used if `parse` contains [`MentionType::Roles`].
     pub roles: Option<Vec<RoleId>>,
     /// A list of user mentions allowed in the message.
     ///
-    /// Mutually exclusive with `parse`.
+    /// Cannot be used if `parse` contains [`MentionType::Users`].
     pub users: Option<Vec<UserId>>,
+    /// Whether to mention the author of the message being replied to.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    #[setters(bool)]
+    pub replied_user: bool,
+}
+impl AllowedMentions {
+    /// Creates a new value that disallows all mentions.
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Allows a type of mention in the message.
+    ///
+    /// See [`parse`](`#structfield.parse`).
+    pub fn allow(mut self, tp: MentionType) -> Self {
+        self.parse.get_or_insert_with(EnumSet::new).insert(tp);
+        self
+    }
+
+    /// Allows a user to be mentioned in the message.
+    ///
+    /// See [`users`](`#structfield.users`).
+    pub fn user(mut self, id: impl Into<UserId>) -> Self {
+        self.users.get_or_insert_with(Vec::new).push(id.into());
+        self
+    }
+
+    /// Allows a role to be mentioned in the message.
+    ///
+    /// See [`roles`](`#structfield.roles`).
+    pub fn role(mut self, id: impl Into<RoleId>) -> Self {
+        self.roles.get_or_insert_with(Vec::new).push(id.into());
+        self
+    }
 }
 
 /// A file to pass to the `Create Messages` endpoint.
@@ -356,7 +570,16 @@ pub struct CreateMessageFile<'a> {
     /// The mime type of the file.
     mime_type: Cow<'a, str>,
     /// The contents of the file.
-    contents: Cow<'a, [u8]>,
+    contents: CreateMessageFileContents<'a>,
+}
+
+/// The source of the contents of a [`CreateMessageFile`].
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+enum CreateMessageFileContents<'a> {
+    /// The contents are held in memory.
+    Memory(Cow<'a, [u8]>),
+    /// The contents are streamed from a file on disk when the message is sent.
+    Path(PathBuf, u64),
 }
 impl <'a> CreateMessageFile<'a> {
     /// Create a new file, guessing the mime type from the file extension.
@@ -379,21 +602,46 @@ impl <'a> CreateMessageFile<'a> {
         CreateMessageFile {
             file_name: file_name.into(),
             mime_type: mime_type.into(),
-            contents: contents.into(),
+            contents: CreateMessageFileContents::Memory(contents.into()),
         }
     }
 
     /// Creates a new file from a file on the disk.
+    ///
+    /// This reads the entire file into memory. For large files, consider using
+    /// [`new_from_file_streaming`](`CreateMessageFile::new_from_file_streaming`) instead.
     pub fn new_from_file(path: impl AsRef<Path>) -> Result<Self> {
         Self::new_from_file_0(path.as_ref())
     }
     fn new_from_file_0(path: &Path) -> Result<Self> {
+        let (path, file_name, mime) = Self::check_path(path)?;
+        let contents = std::fs::read(&path).io_err("Could not read given file.")?;
+        Ok(Self::new_with_mime(file_name, mime, contents))
+    }
+
+    /// Creates a new file from a file on the disk, without reading it into memory.
+    ///
+    /// The file is instead streamed from the disk each time the message is sent. It should not
+    /// be modified or removed until the message has been sent.
+    pub fn new_from_file_streaming(path: impl AsRef<Path>) -> Result<Self> {
+        Self::new_from_file_streaming_0(path.as_ref())
+    }
+    fn new_from_file_streaming_0(path: &Path) -> Result<Self> {
+        let (path, file_name, mime) = Self::check_path(path)?;
+        let size = std::fs::metadata(&path).io_err("Could not read given file.")?.len();
+        Ok(CreateMessageFile {
+            file_name: file_name.into(),
+            mime_type: mime.into(),
+            contents: CreateMessageFileContents::Path(path, size),
+        })
+    }
+
+    fn check_path(path: &Path) -> Result<(PathBuf, String, &'static str)> {
         let path = std::fs::canonicalize(path).io_err("Could not canonicalize given path.")?;
         ensure!(path.is_file(), IoError, "Given path is not a file.");
         let file_name = path.file_name().unexpected()?.to_string_lossy().to_string();
         let mime = mime_guess::from_path(&path).first_raw().unwrap_or("application/octet-stream");
-        let contents = std::fs::read(&path).io_err("Could not read given file.")?;
-        Ok(Self::new_with_mime(file_name, mime, contents))
+        Ok((path, file_name, mime))
     }
 
     /// Returns the name of this file.
@@ -407,8 +655,32 @@ impl <'a> CreateMessageFile<'a> {
     }
 
     /// Returns the contents of this file.
+    ///
+    /// This is empty for files streamed from the disk, as their contents are only read when the
+    /// message is sent. Use [`path`](`CreateMessageFile::path`) to find where they are read from.
     pub fn contents(&self) -> &[u8] {
-        &self.contents
+        match &self.contents {
+            CreateMessageFileContents::Memory(contents) => contents,
+            CreateMessageFileContents::Path(..) => &[],
+        }
+    }
+
+    /// Returns the path this file is streamed from, or `None` if it is held in memory.
+    pub fn path(&self) -> Option<&Path> {
+        match &self.contents {
+            CreateMessageFileContents::Memory(_) => None,
+            CreateMessageFileContents::Path(path, _) => Some(path),
+        }
+    }
+
+    /// Returns the size of this file in bytes.
+    ///
+    /// For files streamed from the disk, this is the size of the file when it was created.
+    pub fn size(&self) -> u64 {
+        match &self.contents {
+            CreateMessageFileContents::Memory(contents) => contents.len() as u64,
+            CreateMessageFileContents::Path(_, size) => *size,
+        }
     }
 }
 
@@ -439,20 +711,39 @@ pub struct GetReactionsParams<'a> {
 new_from_default!(GetReactionsParams);
 
 /// The parameters of the `Edit Message` endpoint.
+///
+/// The `content`, `embed`, `embeds` and `components` fields can be in one of three states:
+/// `None` leaves the field of the message unchanged, `Some(Some(_))` replaces it, and
+/// `Some(None)` clears it.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
 #[derive(Setters)]
 #[setters(strip_option, generate_private = "false")]
 #[non_exhaustive]
 pub struct EditMessageParams<'a> {
-    /// The new contents of the message.
+    /// The new contents of the message, or `None` to remove them.
     #[setters(into)]
-    pub content: Option<Cow<'a, str>>,
-    /// The new embed of the message.
-    pub embed: Option<Embed<'a>>,
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub content: Option<Option<Cow<'a, str>>>,
+    /// The new embed of the message, or `None` to remove it.
+    ///
+    /// This is kept for compatibility. If `embeds` is also set, this embed is sent before them.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub embed: Option<Option<Embed<'a>>>,
+    /// The new embeds of the message, or `None` to remove them.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub embeds: Option<Option<Vec<Embed<'a>>>>,
+    /// The new components of the message, or `None` to remove them.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub components: Option<Option<Vec<Component>>>,
     /// The new flags of the message.
     #[setters(into)]
     pub flags: Option<EnumSet<MessageFlag>>,
+    /// The types of mentions allowed in the message.
+    pub allowed_mentions: Option<AllowedMentions>,
 }
 new_from_default!(EditMessageParams);
 
@@ -464,9 +755,11 @@ new_from_default!(EditMessageParams);
 pub struct EditChannelPermissionsParams<'a> {
     /// A set of permissions that are explicitly allowed.
     #[setters(into)]
+    #[serde(with = "utils::permissions")]
     pub allow: EnumSet<Permission>,
     /// A set of permissions that are explicitly denied.
     #[setters(into)]
+    #[serde(with = "utils::permissions")]
     pub deny: EnumSet<Permission>,
     #[serde(skip)]
     phantom: PhantomData<&'a ()>,
@@ -518,6 +811,41 @@ impl <'a> GroupDmAddRecipientParams<'a> {
     }
 }
 
+/// The parameters of the `Start Thread in Forum Channel` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct StartThreadInForumParams<'a> {
+    /// The name of the thread.
+    #[setters(into)]
+    pub name: Cow<'a, str>,
+    /// The number of minutes of inactivity after which the thread is archived.
+    ///
+    /// Currently limited to 60, 1440, 4320 or 10080 minutes.
+    pub auto_archive_duration: Option<u32>,
+    /// How many seconds a user has to wait before sending another message. Ranges from 0-21600.
+    pub rate_limit_per_user: Option<u32>,
+    /// The initial message of the thread.
+    pub message: CreateMessageParams<'a>,
+    /// The tags to apply to the thread.
+    #[setters(into)]
+    pub applied_tags: Option<Cow<'a, [ForumTagId]>>,
+}
+impl <'a> StartThreadInForumParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(name: impl Into<Cow<'a, str>>, message: CreateMessageParams<'a>) -> Self {
+        StartThreadInForumParams {
+            name: name.into(),
+            auto_archive_duration: None,
+            rate_limit_per_user: None,
+            message,
+            applied_tags: None,
+        }
+    }
+}
+
 /// The parameters of the `Create Guild Emoji` endpoint.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -562,6 +890,58 @@ pub struct ModifyGuildEmojiParams<'a> {
 }
 new_from_default!(ModifyGuildEmojiParams);
 
+/// The parameters of the `Create Guild Sticker` endpoint.
+///
+/// These are sent as form fields alongside the sticker file rather than as JSON.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct CreateGuildStickerParams<'a> {
+    /// The name of the sticker.
+    #[setters(into)]
+    pub name: Cow<'a, str>,
+    /// The description of the sticker.
+    #[setters(into)]
+    pub description: Cow<'a, str>,
+    /// The name of an emoji related to the sticker, used to suggest it.
+    #[setters(into)]
+    pub tags: Cow<'a, str>,
+}
+impl <'a> CreateGuildStickerParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(
+        name: impl Into<Cow<'a, str>>,
+        description: impl Into<Cow<'a, str>>,
+        tags: impl Into<Cow<'a, str>>,
+    ) -> Self {
+        CreateGuildStickerParams {
+            name: name.into(),
+            description: description.into(),
+            tags: tags.into(),
+        }
+    }
+}
+
+/// The parameters of the `Modify Guild Sticker` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyGuildStickerParams<'a> {
+    /// The name of the sticker.
+    #[setters(into)]
+    pub name: Option<Cow<'a, str>>,
+    /// The description of the sticker.
+    #[setters(into)]
+    pub description: Option<Cow<'a, str>>,
+    /// The name of an emoji related to the sticker, used to suggest it.
+    #[setters(into)]
+    pub tags: Option<Cow<'a, str>>,
+}
+new_from_default!(ModifyGuildStickerParams);
+
 /// The parameters of the `Create Guild` endpoint.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -711,11 +1091,19 @@ pub struct ModifyGuildChannelPositionParams {
     pub id: ChannelId,
     /// The new position of the channel.
     pub position: u32,
+    /// Whether to sync the channel's permission overwrites with its new parent category.
+    pub lock_permissions: Option<bool>,
+    /// The new parent category of the channel, or `None` to remove it from its category.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<Option<ChannelId>>,
 }
 impl ModifyGuildChannelPositionParams {
     #[allow(missing_docs)]
     pub fn new(id: impl Into<ChannelId>, position: u32) -> Self {
-        ModifyGuildChannelPositionParams { id: id.into(), position }
+        ModifyGuildChannelPositionParams {
+            id: id.into(), position, lock_permissions: None, parent_id: None,
+        }
     }
 }
 
@@ -738,6 +1126,28 @@ pub struct ListGuildMembersParams<'a> {
 }
 new_from_default!(ListGuildMembersParams);
 
+/// The parameters of the `Search Guild Members` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct SearchGuildMembersParams<'a> {
+    /// The string to match usernames and nicknames against.
+    #[setters(into)]
+    pub query: Cow<'a, str>,
+    /// The number of users to return.
+    ///
+    /// Currently limited to 1-1000 users. Defaults to 1 users.
+    pub limit: Option<u32>,
+}
+impl <'a> SearchGuildMembersParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(query: impl Into<Cow<'a, str>>) -> Self {
+        SearchGuildMembersParams { query: query.into(), limit: None }
+    }
+}
+
 /// The parameters of the `Add Guild Member` endpoint.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
@@ -788,6 +1198,12 @@ pub struct ModifyGuildMemberParams<'a> {
     #[setters(into)]
     #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
     pub channel_id: Option<Option<ChannelId>>,
+    /// When the user's timeout will expire, or `None` to remove the timeout.
+    ///
+    /// This may be set to at most 28 days in the future.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub communication_disabled_until: Option<Option<DateTime<Utc>>>,
 }
 new_from_default!(ModifyGuildMemberParams);
 
@@ -821,6 +1237,7 @@ pub struct GuildRoleParams<'a> {
 	pub name: Option<Cow<'a, str>>,
     /// The permissions granted to the role.
     #[setters(into)]
+    #[serde(default, with = "utils::permissions_opt")]
 	pub permissions: Option<EnumSet<Permission>>,
     /// The color of the role.
 	#[setters(into)]
@@ -888,6 +1305,209 @@ impl <'a> From<GetGuildPruneCountParams<'a>> for BeginGuildPruneParams<'a> {
 }
 new_from_default!(BeginGuildPruneParams);
 
+/// The parameters of the `Create Stage Instance` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct CreateStageInstanceParams<'a> {
+    /// The topic of the stage instance.
+    #[setters(into)]
+    pub topic: Cow<'a, str>,
+    /// Who can see the stage instance.
+    pub privacy_level: Option<StagePrivacyLevel>,
+}
+impl <'a> CreateStageInstanceParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(topic: impl Into<Cow<'a, str>>) -> Self {
+        CreateStageInstanceParams { topic: topic.into(), privacy_level: None }
+    }
+}
+
+/// The parameters of the `Modify Stage Instance` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyStageInstanceParams<'a> {
+    /// The topic of the stage instance.
+    #[setters(into)]
+    pub topic: Option<Cow<'a, str>>,
+    /// Who can see the stage instance.
+    pub privacy_level: Option<StagePrivacyLevel>,
+}
+new_from_default!(ModifyStageInstanceParams);
+
+/// The parameters of the `List Scheduled Events for Guild` or `Get Guild Scheduled Event`
+/// endpoints.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct GetScheduledEventParams<'a> {
+    /// Whether to include the number of users subscribed to each scheduled event.
+    pub with_user_count: Option<bool>,
+    #[serde(skip)]
+    phantom: PhantomData<&'a ()>,
+}
+new_from_default!(GetScheduledEventParams);
+
+/// The parameters of the `Create Guild Scheduled Event` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct CreateScheduledEventParams<'a> {
+    /// The channel the scheduled event takes place in. Required for stage and voice events.
+    #[setters(into)]
+    pub channel_id: Option<ChannelId>,
+    /// Additional information about where the scheduled event takes place. Required for
+    /// external events.
+    pub entity_metadata: Option<ScheduledEventEntityMetadata>,
+    /// The name of the scheduled event.
+    #[setters(into)]
+    pub name: Cow<'a, str>,
+    /// Who can see the scheduled event.
+    pub privacy_level: ScheduledEventPrivacyLevel,
+    /// When the scheduled event starts.
+    #[setters(into)]
+    pub scheduled_start_time: DateTime<Utc>,
+    /// When the scheduled event ends. Required for external events.
+    #[setters(into)]
+    pub scheduled_end_time: Option<DateTime<Utc>>,
+    /// The description of the scheduled event.
+    #[setters(into)]
+    pub description: Option<Cow<'a, str>>,
+    /// Where the scheduled event takes place.
+    pub entity_type: ScheduledEventEntityType,
+}
+impl <'a> CreateScheduledEventParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(
+        name: impl Into<Cow<'a, str>>,
+        scheduled_start_time: impl Into<DateTime<Utc>>,
+        entity_type: ScheduledEventEntityType,
+    ) -> Self {
+        CreateScheduledEventParams {
+            channel_id: None,
+            entity_metadata: None,
+            name: name.into(),
+            privacy_level: ScheduledEventPrivacyLevel::GuildOnly,
+            scheduled_start_time: scheduled_start_time.into(),
+            scheduled_end_time: None,
+            description: None,
+            entity_type,
+        }
+    }
+}
+
+/// The parameters of the `Modify Guild Scheduled Event` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyScheduledEventParams<'a> {
+    /// The channel the scheduled event takes place in, or `None` for external events.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub channel_id: Option<Option<ChannelId>>,
+    /// Additional information about where the scheduled event takes place.
+    pub entity_metadata: Option<ScheduledEventEntityMetadata>,
+    /// The name of the scheduled event.
+    #[setters(into)]
+    pub name: Option<Cow<'a, str>>,
+    /// Who can see the scheduled event.
+    pub privacy_level: Option<ScheduledEventPrivacyLevel>,
+    /// When the scheduled event starts.
+    #[setters(into)]
+    pub scheduled_start_time: Option<DateTime<Utc>>,
+    /// When the scheduled event ends.
+    #[setters(into)]
+    pub scheduled_end_time: Option<DateTime<Utc>>,
+    /// The description of the scheduled event.
+    #[setters(into)]
+    pub description: Option<Cow<'a, str>>,
+    /// Where the scheduled event takes place.
+    pub entity_type: Option<ScheduledEventEntityType>,
+    /// The status of the scheduled event.
+    ///
+    /// Scheduled events can only be started or canceled while scheduled, and can only be
+    /// completed while active.
+    pub status: Option<ScheduledEventStatus>,
+}
+new_from_default!(ModifyScheduledEventParams);
+
+/// The parameters of the `Get Guild Scheduled Event Users` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct GetScheduledEventUsersParams<'a> {
+    /// The number of users to return.
+    ///
+    /// Currently limited to 1-100 users. Defaults to 100 users.
+    pub limit: Option<u32>,
+    /// Whether to include the guild member information of each user.
+    pub with_member: Option<bool>,
+    /// Gets users before the user ID.
+    ///
+    /// Mutually exclusive with `after`.
+    #[setters(into)]
+    pub before: Option<UserId>,
+    /// Gets users after the user ID.
+    ///
+    /// Mutually exclusive with `before`.
+    #[setters(into)]
+    pub after: Option<UserId>,
+    #[serde(skip)]
+    phantom: PhantomData<&'a ()>,
+}
+new_from_default!(GetScheduledEventUsersParams);
+
+/// The parameters of the `Get Guild Audit Log` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct GetGuildAuditLogParams<'a> {
+    /// Only returns entries for actions performed by this user.
+    #[setters(into)]
+    pub user_id: Option<UserId>,
+    /// Only returns entries for this type of action.
+    pub action_type: Option<AuditLogEvent>,
+    /// Gets entries before the entry ID.
+    #[setters(into)]
+    pub before: Option<AuditLogEntryId>,
+    /// The number of entries to return.
+    ///
+    /// Currently limited to 1-100 entries. Defaults to 50 entries.
+    pub limit: Option<u32>,
+    #[serde(skip)]
+    phantom: PhantomData<&'a ()>,
+}
+new_from_default!(GetGuildAuditLogParams);
+
+/// The parameters of the `Get Guild` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct GetGuildParams<'a> {
+    /// Whether to return the approximate member and presence counts of the guild.
+    pub with_counts: Option<bool>,
+    #[serde(skip)]
+    phantom: PhantomData<&'a ()>,
+}
+new_from_default!(GetGuildParams);
+
 /// The parameters of the `Modify Guild Embed` endpoint.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
@@ -904,6 +1524,42 @@ pub struct ModifyGuildEmbedParams<'a> {
 }
 new_from_default!(ModifyGuildEmbedParams);
 
+/// The parameters of the `Modify Guild Widget` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyGuildWidgetParams<'a> {
+    /// Whether the guild widget is enabled.
+    pub enabled: Option<bool>,
+    /// The channel ID invites from the widget are created for.
+    #[setters(into)]
+    pub channel_id: Option<ChannelId>,
+    #[serde(skip)]
+    phantom: PhantomData<&'a ()>,
+}
+new_from_default!(ModifyGuildWidgetParams);
+
+/// The parameters of the `Modify Guild Welcome Screen` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyGuildWelcomeScreenParams<'a> {
+    /// Whether the welcome screen is enabled.
+    pub enabled: Option<bool>,
+    /// The channels shown in the welcome screen.
+    #[setters(into)]
+    pub welcome_channels: Option<Cow<'a, [WelcomeScreenChannel]>>,
+    /// The description of the guild shown in the welcome screen, or `None` to remove it.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Option<Cow<'a, str>>>,
+}
+new_from_default!(ModifyGuildWelcomeScreenParams);
+
 /// The return value of the `Get Guild Vanity URL` endpoint.
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[non_exhaustive]
@@ -920,6 +1576,65 @@ pub struct GuildPruneInfo {
     pub pruned: Option<u32>,
 }
 
+/// The parameters of the `Create Guild from Guild Template` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct CreateGuildFromTemplateParams<'a> {
+    /// The name of the guild.
+    #[setters(into)]
+    pub name: Cow<'a, str>,
+    /// The icon of the guild.
+    #[setters(into)]
+    pub icon: Option<ImageData<'a>>,
+}
+impl <'a> CreateGuildFromTemplateParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
+        CreateGuildFromTemplateParams { name: name.into(), icon: None }
+    }
+}
+
+/// The parameters of the `Create Guild Template` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct CreateGuildTemplateParams<'a> {
+    /// The name of the template.
+    #[setters(into)]
+    pub name: Cow<'a, str>,
+    /// The description of the template.
+    #[setters(into)]
+    pub description: Option<Cow<'a, str>>,
+}
+impl <'a> CreateGuildTemplateParams<'a> {
+    /// Create a new instance from the required parameters.
+    pub fn new(name: impl Into<Cow<'a, str>>) -> Self {
+        CreateGuildTemplateParams { name: name.into(), description: None }
+    }
+}
+
+/// The parameters of the `Modify Guild Template` endpoint.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct ModifyGuildTemplateParams<'a> {
+    /// The new name of the template.
+    #[setters(into)]
+    pub name: Option<Cow<'a, str>>,
+    /// The new description of the template, or `None` to remove it.
+    #[setters(into)]
+    #[serde(with = "utils::option_option", skip_serializing_if = "Option::is_none")]
+    pub description: Option<Option<Cow<'a, str>>>,
+}
+new_from_default!(ModifyGuildTemplateParams);
+
 /// The parameters of the `Get Invite` endpoint.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
@@ -929,6 +1644,8 @@ pub struct GuildPruneInfo {
 pub struct GetInviteParams<'a> {
     /// Whether to return approximate member counts.
     pub with_counts: Option<bool>,
+    /// Whether to return the expiration date of the invite.
+    pub with_expiration: Option<bool>,
     #[serde(skip)]
     phantom: PhantomData<&'a ()>,
 }
@@ -950,6 +1667,15 @@ pub struct ModifyCurrentUserParams<'a> {
 new_from_default!(ModifyCurrentUserParams);
 
 /// The parameters of the `Get Current User Guilds` endpoint.
+///
+/// # Example
+///
+/// ```rust
+/// # use minnie_model::http::GetCurrentUserGuildsParams;
+/// let params = GetCurrentUserGuildsParams::new().limit(50).with_counts(true);
+/// let query = serde_urlencoded::to_string(&params).unwrap();
+/// assert!(query.contains("with_counts=true"));
+/// ```
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
 #[derive(Setters)]
@@ -970,6 +1696,8 @@ pub struct GetCurrentUserGuildsParams<'a> {
     ///
     /// Currently limited to 1-100 guilds. Defaults to 100 guilds.
     pub limit: Option<u32>,
+    /// Whether to return the approximate member and presence counts of each guild.
+    pub with_counts: Option<bool>,
     #[serde(skip)]
     phantom: PhantomData<&'a ()>,
 }
@@ -0,0 +1,206 @@
+//! Types related to Discord interactions.
+
+use crate::guild::*;
+use crate::http::AllowedMentions;
+use crate::message::*;
+use crate::serde::*;
+use crate::types::*;
+use crate::user::*;
+use std::borrow::Cow;
+
+enum_with_unknown! {
+    /// The type of an interaction.
+    ///
+    /// Interaction types not known to this library are parsed as
+    /// [`Unknown`](`InteractionType::Unknown`) rather than causing an error.
+    pub enum InteractionType(u8) {
+        /// A ping sent by Discord to check that an interactions endpoint is working.
+        Ping = 1,
+        /// A user used an application command.
+        ApplicationCommand = 2,
+        /// A user interacted with a message component.
+        MessageComponent = 3,
+        /// A user is typing an application command option that uses autocomplete.
+        ApplicationCommandAutocomplete = 4,
+        /// A user submitted a modal.
+        ModalSubmit = 5,
+    }
+}
+
+/// The value of an application command option.
+///
+/// Option values may be strings, integers, floating point numbers or booleans depending on the
+/// type of the option, so this stores the raw JSON of the value.
+pub type InteractionOptionValue = RawJson;
+
+/// An option of an application command used in an interaction.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct InteractionDataOption {
+    /// The name of the option.
+    pub name: String,
+    /// The raw type of the option.
+    #[serde(rename = "type")]
+    pub option_type: u8,
+    /// The value of the option, if it is not a subcommand or subcommand group.
+    pub value: Option<InteractionOptionValue>,
+    /// The options of a subcommand or subcommand group.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<InteractionDataOption>,
+    /// Whether this option is the one currently being autocompleted.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    pub focused: bool,
+}
+
+/// The data of an interaction.
+///
+/// Which fields are present depends on the type of the interaction.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct InteractionData {
+    /// The ID of the application command used.
+    pub id: Option<Snowflake>,
+    /// The name of the application command used.
+    pub name: Option<String>,
+    /// The options the application command was used with.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<InteractionDataOption>,
+    /// The ID of the user or message a context menu command was used on.
+    pub target_id: Option<Snowflake>,
+    /// The custom ID of the message component or modal interacted with.
+    pub custom_id: Option<String>,
+    /// The type of the message component interacted with.
+    pub component_type: Option<ComponentType>,
+    /// The values selected in a select menu.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub values: Vec<String>,
+}
+
+/// An interaction with the bot, such as an application command being used.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct Interaction {
+    /// The ID of the interaction.
+    pub id: InteractionId,
+    /// The ID of the application the interaction is for.
+    pub application_id: ApplicationId,
+    /// The type of the interaction.
+    #[serde(rename = "type")]
+    pub interaction_type: InteractionType,
+    /// The data of the interaction.
+    pub data: Option<InteractionData>,
+    /// The guild the interaction was sent from, if any.
+    pub guild_id: Option<GuildId>,
+    /// The channel the interaction was sent from.
+    pub channel_id: Option<ChannelId>,
+    /// The member that sent the interaction, if it was sent from a guild.
+    pub member: Option<Member>,
+    /// The user that sent the interaction, if it was sent from a DM.
+    pub user: Option<User>,
+    /// The token used to respond to the interaction.
+    pub token: InteractionToken,
+    /// The message a message component interaction was sent from.
+    pub message: Option<Box<Message>>,
+}
+into_id!(Interaction, InteractionId, id);
+impl Interaction {
+    /// Returns the user that sent the interaction, whether it was sent from a guild or a DM.
+    pub fn sender(&self) -> Option<&User> {
+        self.member.as_ref().map(|x| &x.user).or(self.user.as_ref())
+    }
+}
+
+enum_with_unknown! {
+    /// The type of a response to an interaction.
+    pub enum InteractionCallbackType(u8) {
+        /// Acknowledges a [`Ping`](`InteractionType::Ping`).
+        Pong = 1,
+        /// Responds with a message.
+        ChannelMessageWithSource = 4,
+        /// Acknowledges the interaction, and shows a loading state until a message is sent by
+        /// editing the original response.
+        DeferredChannelMessageWithSource = 5,
+        /// Acknowledges a component interaction, to edit the message it was sent from later.
+        DeferredUpdateMessage = 6,
+        /// Edits the message a component interaction was sent from.
+        UpdateMessage = 7,
+    }
+}
+
+/// The message sent or edited by a response to an interaction.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash, Default)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct InteractionCallbackData<'a> {
+    /// Whether to enable text to speech.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    pub tts: bool,
+    /// The contents of the message.
+    #[setters(into)]
+    pub content: Option<Cow<'a, str>>,
+    /// The embeds of the message.
+    pub embeds: Option<Vec<Embed<'a>>>,
+    /// The components of the message.
+    pub components: Option<Vec<Component>>,
+    /// The types of mentions allowed in the message.
+    pub allowed_mentions: Option<AllowedMentions>,
+    /// The flags of the message. Only [`MessageFlag::Ephemeral`] may be set.
+    #[setters(into)]
+    pub flags: Option<EnumSet<MessageFlag>>,
+}
+new_from_default!(InteractionCallbackData);
+
+/// A response to an interaction.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct InteractionResponse<'a> {
+    /// The type of the response.
+    #[serde(rename = "type")]
+    pub response_type: InteractionCallbackType,
+    /// The message sent or edited by the response.
+    pub data: Option<InteractionCallbackData<'a>>,
+}
+impl <'a> InteractionResponse<'a> {
+    /// Creates a new response with no message data.
+    pub fn new(response_type: InteractionCallbackType) -> Self {
+        InteractionResponse { response_type, data: None }
+    }
+
+    /// Creates a response acknowledging a ping.
+    pub fn pong() -> Self {
+        Self::new(InteractionCallbackType::Pong)
+    }
+
+    /// Creates a response that sends a message.
+    pub fn message(data: InteractionCallbackData<'a>) -> Self {
+        InteractionResponse {
+            response_type: InteractionCallbackType::ChannelMessageWithSource,
+            data: Some(data),
+        }
+    }
+
+    /// Creates a response that shows a loading state until the original response is edited.
+    pub fn deferred_message() -> Self {
+        Self::new(InteractionCallbackType::DeferredChannelMessageWithSource)
+    }
+
+    /// Creates a response that acknowledges a component interaction without editing its
+    /// message yet.
+    pub fn deferred_update() -> Self {
+        Self::new(InteractionCallbackType::DeferredUpdateMessage)
+    }
+
+    /// Creates a response that edits the message a component interaction was sent from.
+    pub fn update_message(data: InteractionCallbackData<'a>) -> Self {
+        InteractionResponse {
+            response_type: InteractionCallbackType::UpdateMessage,
+            data: Some(data),
+        }
+    }
+}
@@ -3,7 +3,6 @@
 //! This is reexposed in `minnie`, and that should be preferred over this crate.
 
 // TODO: Add documentation for individual fields in the model.
-// TODO: Handle malformed presence updates only for fields that might be incorrect.
 // TODO: Add better methods for retrieving/etc image data.
 
 #[macro_use] mod serde;
@@ -23,11 +22,13 @@ macro_rules! into_id {
     }
 }
 
+pub mod audit_log;
 pub mod channel;
 pub mod event;
 pub mod gateway;
 pub mod guild;
 pub mod http;
+pub mod interaction;
 pub mod message;
 pub mod types;
 pub mod user;
\ No newline at end of file
@@ -6,6 +6,7 @@ use crate::types::*;
 use crate::guild::*;
 use crate::user::*;
 use crate::serde::*;
+use minnie_errors::*;
 use std::borrow::Cow;
 use std::fmt;
 
@@ -88,6 +89,166 @@ impl <'a> Embed<'a> {
 		self.fields.to_mut().push(EmbedField::new(name, value).inline());
 		self
 	}
+
+	/// Creates a new builder for an embed.
+	pub fn builder() -> EmbedBuilder<'a> {
+		EmbedBuilder::new()
+	}
+
+	/// Returns the combined length of the text in the embed, in characters.
+	///
+	/// This counts the title, description, field names and values, footer text and author name,
+	/// and is limited to [`EMBED_TOTAL_LIMIT`] characters by Discord.
+	pub fn total_length(&self) -> usize {
+		let mut len = 0;
+		len += self.title.as_ref().map_or(0, |x| char_len(x));
+		len += self.description.as_ref().map_or(0, |x| char_len(x));
+		for field in self.fields.iter() {
+			len += char_len(&field.name) + char_len(&field.value);
+		}
+		len += self.footer.as_ref().map_or(0, |x| char_len(&x.text));
+		len += self.author.as_ref().and_then(|x| x.name.as_ref()).map_or(0, |x| char_len(x));
+		len
+	}
+
+	/// Checks that the combined length of the embed is within Discord's limits.
+	pub fn check_total_length(&self) -> Result<()> {
+		ensure!(self.total_length() <= EMBED_TOTAL_LIMIT,
+		        InvalidInput, "Embeds cannot contain more than 6000 characters in total.");
+		Ok(())
+	}
+}
+
+/// The maximum length of an embed's title.
+pub const EMBED_TITLE_LIMIT: usize = 256;
+/// The maximum length of an embed's description.
+pub const EMBED_DESCRIPTION_LIMIT: usize = 4096;
+/// The maximum number of fields in an embed.
+pub const EMBED_FIELD_COUNT_LIMIT: usize = 25;
+/// The maximum length of the name of an embed field.
+pub const EMBED_FIELD_NAME_LIMIT: usize = 256;
+/// The maximum length of the value of an embed field.
+pub const EMBED_FIELD_VALUE_LIMIT: usize = 1024;
+/// The maximum length of an embed's footer text.
+pub const EMBED_FOOTER_LIMIT: usize = 2048;
+/// The maximum length of an embed's author name.
+pub const EMBED_AUTHOR_LIMIT: usize = 256;
+/// The maximum combined length of all text in an embed.
+pub const EMBED_TOTAL_LIMIT: usize = 6000;
+/// The maximum number of embeds in a message.
+pub const MESSAGE_EMBED_COUNT_LIMIT: usize = 10;
+
+fn char_len(s: &str) -> usize {
+	s.chars().count()
+}
+
+/// A builder for [`Embed`]s that checks Discord's documented length limits.
+///
+/// Instances can be obtained via [`Embed::builder`] or [`EmbedBuilder::new`].
+#[derive(Clone, Debug, Default)]
+pub struct EmbedBuilder<'a> {
+	embed: Embed<'a>,
+}
+impl <'a> EmbedBuilder<'a> {
+	/// Creates a new empty embed builder.
+	pub fn new() -> Self {
+		EmbedBuilder::default()
+	}
+
+	/// Sets the title of the embed.
+	pub fn title(mut self, title: impl Into<Cow<'a, str>>) -> Self {
+		self.embed.title = Some(title.into());
+		self
+	}
+
+	/// Sets the description of the embed.
+	pub fn description(mut self, description: impl Into<Cow<'a, str>>) -> Self {
+		self.embed.description = Some(description.into());
+		self
+	}
+
+	/// Sets the URL the embed's title links to.
+	pub fn url(mut self, url: impl Into<Cow<'a, str>>) -> Self {
+		self.embed.url = Some(url.into());
+		self
+	}
+
+	/// Sets the color of the embed.
+	pub fn color(mut self, color: impl Into<Color>) -> Self {
+		self.embed.color = Some(color.into());
+		self
+	}
+
+	/// Sets the timestamp displayed in the embed.
+	pub fn timestamp(mut self, timestamp: impl Into<DateTime<Utc>>) -> Self {
+		self.embed.timestamp = Some(timestamp.into());
+		self
+	}
+
+	/// Sets the footer of the embed.
+	pub fn footer(mut self, footer: impl Into<EmbedFooter<'a>>) -> Self {
+		self.embed.footer = Some(footer.into());
+		self
+	}
+
+	/// Sets the image of the embed.
+	pub fn image(mut self, image: impl Into<EmbedImage<'a>>) -> Self {
+		self.embed.image = Some(image.into());
+		self
+	}
+
+	/// Sets the thumbnail of the embed.
+	pub fn thumbnail(mut self, thumbnail: impl Into<EmbedImage<'a>>) -> Self {
+		self.embed.thumbnail = Some(thumbnail.into());
+		self
+	}
+
+	/// Sets the author of the embed.
+	pub fn author(mut self, author: impl Into<EmbedAuthor<'a>>) -> Self {
+		self.embed.author = Some(author.into());
+		self
+	}
+
+	/// Adds a new field to the embed.
+	pub fn field(
+		mut self, name: impl Into<Cow<'a, str>>, value: impl Into<Cow<'a, str>>, inline: bool,
+	) -> Self {
+		let mut field = EmbedField::new(name, value);
+		field.inline = inline;
+		self.embed.fields.to_mut().push(field);
+		self
+	}
+
+	/// Checks the embed against Discord's limits and returns it.
+	pub fn build(self) -> Result<Embed<'a>> {
+		let embed = self.embed;
+		if let Some(title) = &embed.title {
+			ensure!(char_len(title) <= EMBED_TITLE_LIMIT,
+			        InvalidInput, "Embed titles cannot be longer than 256 characters.");
+		}
+		if let Some(description) = &embed.description {
+			ensure!(char_len(description) <= EMBED_DESCRIPTION_LIMIT,
+			        InvalidInput, "Embed descriptions cannot be longer than 4096 characters.");
+		}
+		if let Some(footer) = &embed.footer {
+			ensure!(char_len(&footer.text) <= EMBED_FOOTER_LIMIT,
+			        InvalidInput, "Embed footers cannot be longer than 2048 characters.");
+		}
+		if let Some(name) = embed.author.as_ref().and_then(|x| x.name.as_ref()) {
+			ensure!(char_len(name) <= EMBED_AUTHOR_LIMIT,
+			        InvalidInput, "Embed author names cannot be longer than 256 characters.");
+		}
+		ensure!(embed.fields.len() <= EMBED_FIELD_COUNT_LIMIT,
+		        InvalidInput, "Embeds cannot have more than 25 fields.");
+		for field in embed.fields.iter() {
+			ensure!(char_len(&field.name) <= EMBED_FIELD_NAME_LIMIT,
+			        InvalidInput, "Embed field names cannot be longer than 256 characters.");
+			ensure!(char_len(&field.value) <= EMBED_FIELD_VALUE_LIMIT,
+			        InvalidInput, "Embed field values cannot be longer than 1024 characters.");
+		}
+		embed.check_total_length()?;
+		Ok(embed)
+	}
 }
 
 /// The type of a message embed.
@@ -236,31 +397,368 @@ pub struct Reaction {
 	pub emoji: EmojiRef,
 }
 
-/// The type of a message.
-#[derive(Serialize_repr, Deserialize_repr)]
-#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
-#[repr(i32)]
+enum_with_unknown! {
+    /// The type of a message component.
+    ///
+    /// Component types not known to this library are parsed as
+    /// [`Unknown`](`ComponentType::Unknown`) rather than causing an error.
+    pub enum ComponentType(u8) {
+        /// A row containing other components.
+        ActionRow = 1,
+        /// A clickable button.
+        Button = 2,
+        /// A menu for selecting from a list of options.
+        SelectMenu = 3,
+    }
+}
+
+enum_with_unknown! {
+    /// The style of a button component.
+    pub enum ButtonStyle(u8) {
+        /// A blurple button.
+        Primary = 1,
+        /// A grey button.
+        Secondary = 2,
+        /// A green button.
+        Success = 3,
+        /// A red button.
+        Danger = 4,
+        /// A grey button that navigates to an URL.
+        Link = 5,
+    }
+}
+
+/// An option in a select menu component.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
 #[non_exhaustive]
-pub enum MessageType {
-	Default = 0,
-	RecipientAdd = 1,
-	RecipientRemove = 2,
-	Call = 3,
-	ChannelNameChange = 4,
-	ChannelIconChange = 5,
-	ChannelPinnedMessage = 6,
-	GuildMemberJoin = 7,
-	UserPremiumGuildSubscription = 8,
-	UserPremiumGuildSubscriptionTier1 = 9,
-	UserPremiumGuildSubscriptionTier2 = 10,
-	UserPremiumGuildSubscriptionTier3 = 11,
-	ChannelFollowAdd = 12,
-	GuildDiscoveryDisqualified = 13,
-	GuildDiscoveryRequalified = 14,
-    #[serde(other)]
-    Unknown = i32::max_value(),
+pub struct SelectOption {
+    /// The text shown for the option.
+    #[setters(into)]
+    pub label: String,
+    /// The value sent in the interaction when the option is selected.
+    #[setters(into)]
+    pub value: String,
+    /// An additional description of the option.
+    #[setters(into)]
+    pub description: Option<String>,
+    /// The emoji shown for the option.
+    #[setters(into)]
+    pub emoji: Option<EmojiRef>,
+    /// Whether the option is selected by default.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    #[setters(bool)]
+    pub default: bool,
+}
+impl SelectOption {
+    /// Creates a new option with a label and value.
+    pub fn new(label: impl Into<String>, value: impl Into<String>) -> Self {
+        SelectOption {
+            label: label.into(), value: value.into(), description: None, emoji: None,
+            default: false,
+        }
+    }
 }
 
+/// An interactive component attached to a message, such as a button or select menu.
+///
+/// Which fields are used depends on the type of the component. Messages contain up to
+/// [`MESSAGE_ACTION_ROW_LIMIT`] action rows, which in turn contain the other components. A
+/// [`ComponentBuilder`] can be used to check these limits while assembling components.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
+#[non_exhaustive]
+pub struct Component {
+    /// The type of the component.
+    #[serde(rename = "type")]
+    #[setters(skip)]
+    pub component_type: ComponentType,
+    /// The ID sent in the interaction when a button is clicked or a select menu is used.
+    #[setters(into)]
+    pub custom_id: Option<String>,
+    /// The style of a button.
+    pub style: Option<ButtonStyle>,
+    /// The text shown on a button.
+    #[setters(into)]
+    pub label: Option<String>,
+    /// The emoji shown on a button.
+    #[setters(into)]
+    pub emoji: Option<EmojiRef>,
+    /// The URL a link button navigates to.
+    #[setters(into)]
+    pub url: Option<String>,
+    /// The options of a select menu.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub options: Vec<SelectOption>,
+    /// The text shown in a select menu when nothing is selected.
+    #[setters(into)]
+    pub placeholder: Option<String>,
+    /// The minimum number of options that must be selected in a select menu.
+    pub min_values: Option<u32>,
+    /// The maximum number of options that can be selected in a select menu.
+    pub max_values: Option<u32>,
+    /// Whether the component is disabled.
+    #[serde(default, skip_serializing_if = "utils::if_false")]
+    #[setters(bool)]
+    pub disabled: bool,
+    /// The components in an action row.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[setters(skip)]
+    pub components: Vec<Component>,
+}
+impl Component {
+    fn new(component_type: ComponentType) -> Self {
+        Component {
+            component_type,
+            custom_id: None, style: None, label: None, emoji: None, url: None,
+            options: Vec::new(), placeholder: None, min_values: None, max_values: None,
+            disabled: false, components: Vec::new(),
+        }
+    }
+
+    /// Creates an action row containing the given components.
+    pub fn action_row(components: impl Into<Vec<Component>>) -> Self {
+        let mut row = Component::new(ComponentType::ActionRow);
+        row.components = components.into();
+        row
+    }
+
+    /// Creates a button that sends an interaction with the given custom ID when clicked.
+    pub fn button(
+        style: ButtonStyle, custom_id: impl Into<String>, label: impl Into<String>,
+    ) -> Self {
+        Component::new(ComponentType::Button)
+            .style(style)
+            .custom_id(custom_id)
+            .label(label)
+    }
+
+    /// Creates a button that navigates to an URL when clicked.
+    pub fn link_button(url: impl Into<String>, label: impl Into<String>) -> Self {
+        Component::new(ComponentType::Button)
+            .style(ButtonStyle::Link)
+            .url(url)
+            .label(label)
+    }
+
+    /// Creates a select menu with the given options.
+    pub fn select_menu(
+        custom_id: impl Into<String>, options: impl Into<Vec<SelectOption>>,
+    ) -> Self {
+        let mut menu = Component::new(ComponentType::SelectMenu).custom_id(custom_id);
+        menu.options = options.into();
+        menu
+    }
+}
+
+/// The maximum number of action rows in a message.
+pub const MESSAGE_ACTION_ROW_LIMIT: usize = 5;
+/// The maximum number of buttons in an action row.
+pub const ACTION_ROW_BUTTON_LIMIT: usize = 5;
+
+/// Checks a message's components against Discord's limits.
+///
+/// Top-level components must be action rows. Each action row may contain up to
+/// [`ACTION_ROW_BUTTON_LIMIT`] buttons, or a single select menu.
+pub fn check_components(components: &[Component]) -> Result<()> {
+    ensure!(components.len() <= MESSAGE_ACTION_ROW_LIMIT,
+            InvalidInput, "Messages cannot have more than 5 action rows.");
+    for row in components {
+        ensure!(row.component_type == ComponentType::ActionRow,
+                InvalidInput, "Message components must be placed in action rows.");
+        ensure!(!row.components.is_empty(), InvalidInput, "Action rows cannot be empty.");
+        for component in &row.components {
+            match component.component_type {
+                ComponentType::Button => ensure!(
+                    row.components.len() <= ACTION_ROW_BUTTON_LIMIT,
+                    InvalidInput, "Action rows cannot have more than 5 buttons.",
+                ),
+                ComponentType::SelectMenu => ensure!(
+                    row.components.len() == 1,
+                    InvalidInput, "Select menus must be the only component in their action row.",
+                ),
+                _ => bail!(InvalidInput, "Action rows can only contain buttons or select menus."),
+            }
+        }
+    }
+    Ok(())
+}
+
+/// A builder for the components of a message that checks Discord's documented limits.
+///
+/// Buttons are added to the current action row, and select menus are placed in an action row
+/// of their own.
+///
+/// # Example
+///
+/// ```rust
+/// # use minnie_model::message::*;
+/// let components = ComponentBuilder::new()
+///     .button(Component::button(ButtonStyle::Success, "accept", "Accept"))
+///     .button(Component::button(ButtonStyle::Danger, "decline", "Decline"))
+///     .row()
+///     .button(Component::link_button("https://example.com", "Details"))
+///     .build()
+///     .unwrap();
+/// assert_eq!(components.len(), 2);
+/// assert_eq!(components[0].components.len(), 2);
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct ComponentBuilder {
+    rows: Vec<Vec<Component>>,
+}
+impl ComponentBuilder {
+    /// Creates a new empty component builder.
+    pub fn new() -> Self {
+        ComponentBuilder::default()
+    }
+
+    /// Starts a new action row.
+    pub fn row(mut self) -> Self {
+        self.rows.push(Vec::new());
+        self
+    }
+
+    /// Adds a button to the current action row.
+    pub fn button(mut self, button: Component) -> Self {
+        if self.rows.last().map_or(true, |x| {
+            x.iter().any(|x| x.component_type == ComponentType::SelectMenu)
+        }) {
+            self.rows.push(Vec::new());
+        }
+        self.rows.last_mut().unwrap().push(button);
+        self
+    }
+
+    /// Adds a select menu in a new action row.
+    pub fn select_menu(mut self, menu: Component) -> Self {
+        if self.rows.last().map_or(true, |x| !x.is_empty()) {
+            self.rows.push(Vec::new());
+        }
+        self.rows.last_mut().unwrap().push(menu);
+        self
+    }
+
+    /// Checks the components against Discord's limits and returns them.
+    pub fn build(self) -> Result<Vec<Component>> {
+        let components: Vec<_> = self.rows.into_iter()
+            .filter(|x| !x.is_empty())
+            .map(Component::action_row)
+            .collect();
+        check_components(&components)?;
+        Ok(components)
+    }
+}
+
+enum_with_unknown! {
+    /// The type of a message.
+    ///
+    /// Message types not known to this library are parsed as
+    /// [`Unknown`](`MessageType::Unknown`) rather than causing an error.
+    pub enum MessageType(u32) {
+        Default = 0,
+        RecipientAdd = 1,
+        RecipientRemove = 2,
+        Call = 3,
+        ChannelNameChange = 4,
+        ChannelIconChange = 5,
+        ChannelPinnedMessage = 6,
+        GuildMemberJoin = 7,
+        UserPremiumGuildSubscription = 8,
+        UserPremiumGuildSubscriptionTier1 = 9,
+        UserPremiumGuildSubscriptionTier2 = 10,
+        UserPremiumGuildSubscriptionTier3 = 11,
+        ChannelFollowAdd = 12,
+        GuildDiscoveryDisqualified = 14,
+        GuildDiscoveryRequalified = 15,
+        GuildDiscoveryGracePeriodInitialWarning = 16,
+        GuildDiscoveryGracePeriodFinalWarning = 17,
+        ThreadCreated = 18,
+        Reply = 19,
+        ChatInputCommand = 20,
+        ThreadStarterMessage = 21,
+        GuildInviteReminder = 22,
+        ContextMenuCommand = 23,
+    }
+}
+
+enum_with_unknown! {
+    /// The type of a sticker.
+    pub enum StickerType(u8) {
+        /// An official sticker in a pack.
+        Standard = 1,
+        /// A sticker uploaded to a guild.
+        Guild = 2,
+    }
+}
+
+enum_with_unknown! {
+    /// The file format of a sticker.
+    pub enum StickerFormatType(u8) {
+        /// A PNG image.
+        Png = 1,
+        /// An animated PNG image.
+        Apng = 2,
+        /// A Lottie animation, stored as JSON.
+        Lottie = 3,
+        /// A GIF image.
+        Gif = 4,
+    }
+}
+
+/// A sticker that can be sent in messages.
+#[serde_with::skip_serializing_none]
+#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct Sticker {
+    /// The ID of the sticker.
+    pub id: StickerId,
+    /// The ID of the pack a standard sticker belongs to.
+    pub pack_id: Option<Snowflake>,
+    /// The name of the sticker.
+    pub name: String,
+    /// The description of the sticker.
+    pub description: Option<String>,
+    /// A comma separated list of keywords used to suggest the sticker.
+    pub tags: String,
+    /// The type of the sticker.
+    #[serde(rename = "type")]
+    pub sticker_type: StickerType,
+    /// The file format of the sticker.
+    pub format_type: StickerFormatType,
+    /// Whether a guild sticker can currently be used.
+    pub available: Option<bool>,
+    /// The guild a guild sticker belongs to.
+    pub guild_id: Option<GuildId>,
+    /// The user that uploaded a guild sticker.
+    pub user: Option<User>,
+    /// The sort order of a standard sticker in its pack.
+    pub sort_value: Option<u32>,
+}
+into_id!(Sticker, StickerId, id);
+
+/// The information about a sticker included in a message.
+#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
+#[non_exhaustive]
+pub struct StickerItem {
+    /// The ID of the sticker.
+    pub id: StickerId,
+    /// The name of the sticker.
+    pub name: String,
+    /// The file format of the sticker.
+    pub format_type: StickerFormatType,
+}
+into_id!(StickerItem, StickerId, id);
+
+/// The maximum number of stickers in a message.
+pub const MESSAGE_STICKER_COUNT_LIMIT: usize = 3;
+/// The maximum size of the file uploaded for a guild sticker.
+pub const STICKER_FILE_SIZE_LIMIT: u64 = 512 * 1024;
+
 /// An invitation to join an activity embedded in a message.
 #[serde_with::skip_serializing_none]
 #[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
@@ -297,14 +795,39 @@ pub struct MessageApplication {
 }
 into_id!(MessageApplication, ApplicationId, id);
 
-/// The origin of a crossposted message.
+/// The origin of a crossposted message, or the message a reply refers to.
 #[serde_with::skip_serializing_none]
-#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Hash)]
+#[derive(Serialize, Deserialize, Clone, PartialOrd, Ord, Eq, PartialEq, Debug, Default, Hash)]
+#[derive(Setters)]
+#[setters(strip_option, generate_private = "false")]
 #[non_exhaustive]
 pub struct MessageReference {
+	#[setters(into)]
 	pub message_id: Option<MessageId>,
-	pub channel_id: ChannelId,
+	#[setters(into)]
+	pub channel_id: Option<ChannelId>,
+	#[setters(into)]
 	pub guild_id: Option<GuildId>,
+	/// Whether sending a reply should fail if the referenced message does not exist.
+	///
+	/// This is only used when creating a message.
+	pub fail_if_not_exists: Option<bool>,
+}
+impl MessageReference {
+	/// Creates a reference to the given message, for use in replies.
+	pub fn new(message_id: impl Into<MessageId>) -> Self {
+		MessageReference {
+			message_id: Some(message_id.into()),
+			channel_id: None,
+			guild_id: None,
+			fail_if_not_exists: None,
+		}
+	}
+}
+impl From<MessageId> for MessageReference {
+	fn from(id: MessageId) -> Self {
+		MessageReference::new(id)
+	}
 }
 
 /// A message flag.
@@ -316,6 +839,9 @@ pub enum MessageFlag {
     SuppressEmbeds = 2,
 	SourceMessageDeleted = 3,
 	Urgent = 4,
+	HasThread = 5,
+	Ephemeral = 6,
+	Loading = 7,
 }
 
 /// The internal representation of a message nonce.
@@ -351,7 +877,7 @@ impl MessageNonce {
 	}
 }
 impl Serialize for MessageNonce {
-	fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+	fn serialize<S>(&self, serializer: S) -> StdResult<S::Ok, S::Error> where S: Serializer {
 		match &self.0 {
 			MessageNonceData::Snowflake(v) => v.serialize(serializer),
 			MessageNonceData::String(v) => v.serialize(serializer),
@@ -359,7 +885,7 @@ impl Serialize for MessageNonce {
 	}
 }
 impl <'de> Deserialize<'de> for MessageNonce {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+    fn deserialize<D>(deserializer: D) -> StdResult<Self, D::Error> where D: Deserializer<'de> {
         deserializer.deserialize_any(NonceVisitor)
     }
 }
@@ -369,7 +895,7 @@ impl <'de> Visitor<'de> for NonceVisitor {
     fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
         formatter.write_str("snowflake")
     }
-    fn visit_str<E>(self, v: &str) -> Result<MessageNonce, E> where E: DeError {
+    fn visit_str<E>(self, v: &str) -> StdResult<MessageNonce, E> where E: DeError {
 		Ok(v.into())
     }
     snowflake_visitor_common!(MessageNonce);
@@ -489,5 +1015,26 @@ pub struct Message {
 	pub message_reference: Option<MessageReference>,
     #[serde(default, skip_serializing_if = "EnumSet::is_empty")]
     pub flags: EnumSet<MessageFlag>,
+    /// The message this message replies to.
+    ///
+    /// This is `None` if the message is not a reply, if the referenced message was deleted, or
+    /// if Discord did not include it in the response.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub referenced_message: Option<Box<Message>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub components: Vec<Component>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub sticker_items: Vec<StickerItem>,
 }
 into_id!(Message, MessageId, id);
+impl Message {
+    /// Returns a link that jumps to this message in the Discord client.
+    pub fn jump_link(&self) -> String {
+        match self.guild_id {
+            Some(guild) => format!(
+                "https://discord.com/channels/{}/{}/{}", guild.0, self.channel_id.0, self.id.0,
+            ),
+            None => format!("https://discord.com/channels/@me/{}/{}", self.channel_id.0, self.id.0),
+        }
+    }
+}
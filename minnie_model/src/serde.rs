@@ -18,6 +18,7 @@ pub use serde_json::{self, Value as JsonValue};
 pub use strum_macros::*;
 
 use std::borrow::Cow;
+use std::fmt;
 
 macro_rules! snowflake_visitor_common {
     ($ty:ty) => {
@@ -55,6 +56,55 @@ macro_rules! snowflake_visitor_common {
     }
 }
 
+macro_rules! enum_with_unknown {
+    (
+        $(#[$meta:meta])*
+        pub enum $name:ident($repr:ty) {
+            $($(#[$var_meta:meta])* $variant:ident = $value:literal,)*
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+        #[non_exhaustive]
+        pub enum $name {
+            $($(#[$var_meta])* $variant,)*
+            /// An unrecognized value.
+            Unknown($repr),
+        }
+        impl $name {
+            /// Converts a raw value into this type.
+            pub fn from_raw(v: $repr) -> Self {
+                match v {
+                    $($value => $name::$variant,)*
+                    v => $name::Unknown(v),
+                }
+            }
+
+            /// Returns the raw value of this type.
+            pub fn to_raw(self) -> $repr {
+                match self {
+                    $($name::$variant => $value,)*
+                    $name::Unknown(v) => v,
+                }
+            }
+        }
+        impl Serialize for $name {
+            fn serialize<S>(
+                &self, serializer: S,
+            ) -> ::std::result::Result<S::Ok, S::Error> where S: Serializer {
+                self.to_raw().serialize(serializer)
+            }
+        }
+        impl <'de> Deserialize<'de> for $name {
+            fn deserialize<D>(
+                deserializer: D,
+            ) -> ::std::result::Result<Self, D::Error> where D: Deserializer<'de> {
+                <$repr>::deserialize(deserializer).map($name::from_raw)
+            }
+        }
+    }
+}
+
 macro_rules! new_from_default {
     ($name:ident) => {
         impl <'a> $name <'a> {
@@ -91,6 +141,57 @@ impl <'a, T: 'a + Clone> PushToCow<T> for Option<Cow<'a, [T]>> {
     }
 }
 
+/// A JSON value whose type is not known in advance, stored as its raw JSON.
+#[derive(Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+pub struct RawJson(String);
+impl RawJson {
+    /// Returns the raw JSON of this value.
+    pub fn as_json(&self) -> &str {
+        &self.0
+    }
+
+    /// Parses this value as a given type, returning `None` if it has a different type.
+    pub fn parse<T: DeserializeOwned>(&self) -> Option<T> {
+        serde_json::from_str(&self.0).ok()
+    }
+
+    /// Returns this value as a string, if it is one.
+    pub fn as_string(&self) -> Option<String> {
+        self.parse()
+    }
+
+    /// Returns this value as an integer, if it is one.
+    pub fn as_i64(&self) -> Option<i64> {
+        self.parse()
+    }
+
+    /// Returns this value as a floating point number, if it is a number.
+    pub fn as_f64(&self) -> Option<f64> {
+        self.parse()
+    }
+
+    /// Returns this value as a boolean, if it is one.
+    pub fn as_bool(&self) -> Option<bool> {
+        self.parse()
+    }
+}
+impl fmt::Debug for RawJson {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+impl Serialize for RawJson {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error> where S: Serializer {
+        let value: JsonValue = serde_json::from_str(&self.0).map_err(S::Error::custom)?;
+        value.serialize(serializer)
+    }
+}
+impl <'de> Deserialize<'de> for RawJson {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error> where D: Deserializer<'de> {
+        Ok(RawJson(JsonValue::deserialize(deserializer)?.to_string()))
+    }
+}
+
 pub mod utils {
     use super::*;
     use std::time::{UNIX_EPOCH, SystemTime, Duration};
@@ -102,6 +203,10 @@ pub mod utils {
     *b
     }
 
+    pub fn one() -> u32 {
+        1
+    }
+
     pub fn cow_is_empty<T>(c: &Cow<'_, [T]>) -> bool where [T]: ToOwned {
         c.is_empty()
     }
@@ -142,6 +247,20 @@ pub mod utils {
         }
     }
 
+    pub mod duration_secs_f64 {
+        use super::*;
+        pub fn serialize<S: Serializer>(t: &Duration, s: S) -> Result<S::Ok, S::Error> {
+            t.as_secs_f64().serialize(s)
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(d: D) -> Result<Duration, D::Error> {
+            let secs = f64::deserialize(d)?;
+            if !secs.is_finite() || secs < 0.0 {
+                return Err(D::Error::custom("duration out of range"))
+            }
+            Ok(Duration::from_secs_f64(secs))
+        }
+    }
+
     pub mod duration_secs {
         use super::*;
         pub fn serialize<S: Serializer>(t: &Duration, s: S) -> Result<S::Ok, S::Error> {
@@ -190,5 +309,46 @@ pub mod utils {
         }
     }
 
+    /// Serializes permissions as a string, and deserializes them from either a string or an
+    /// integer.
+    pub mod permissions {
+        use super::*;
+        use crate::types::Permission;
+        use std::fmt;
+
+        struct PermissionsVisitor;
+        impl <'de> Visitor<'de> for PermissionsVisitor {
+            type Value = EnumSet<Permission>;
+            fn expecting(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+                formatter.write_str("permission bitfield")
+            }
+            fn visit_i64<E>(self, v: i64) -> Result<Self::Value, E> where E: DeError {
+                if v < 0 {
+                    Err(E::custom("permissions cannot be negative"))
+                } else {
+                    Ok(EnumSet::from_u64_truncated(v as u64))
+                }
+            }
+            fn visit_u64<E>(self, v: u64) -> Result<Self::Value, E> where E: DeError {
+                Ok(EnumSet::from_u64_truncated(v))
+            }
+            fn visit_str<E>(self, v: &str) -> Result<Self::Value, E> where E: DeError {
+                Permission::from_bits_str(v).map_err(E::custom)
+            }
+        }
+
+        pub fn serialize<S: Serializer>(
+            t: &EnumSet<Permission>, s: S,
+        ) -> Result<S::Ok, S::Error> {
+            Permission::to_bits_str(*t).serialize(s)
+        }
+        pub fn deserialize<'de, D: Deserializer<'de>>(
+            d: D,
+        ) -> Result<EnumSet<Permission>, D::Error> {
+            d.deserialize_any(PermissionsVisitor)
+        }
+    }
+
     option_wrapper!(system_time_millis_opt, "system_time_millis", SystemTime);
+    option_wrapper!(permissions_opt, "permissions", EnumSet<crate::types::Permission>);
 }
\ No newline at end of file
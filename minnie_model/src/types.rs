@@ -1,5 +1,6 @@
 //! Basic types common to all API calls.
 
+use chrono::{DateTime, Utc};
 use crate::serde::*;
 use fxhash::FxHasher;
 use http::header::HeaderValue;
@@ -9,11 +10,25 @@ use std::borrow::Cow;
 use std::fmt;
 use std::hash::{Hash, Hasher};
 use std::ops::Deref;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
+pub use crate::serde::RawJson;
+
 /// A permission that a user may have.
+///
+/// Permissions use the full 64-bit range of Discord's permission bitfields:
+///
+/// ```rust
+/// # use minnie_model::types::Permission;
+/// # use enumset::EnumSet;
+/// let perms: EnumSet<Permission> = Permission::ManageThreads | Permission::SendMessages;
+/// let json = serde_json::to_string(&perms).unwrap();
+/// assert_eq!(serde_json::from_str::<EnumSet<Permission>>(&json).unwrap(), perms);
+/// assert_eq!(Permission::from_bits_str(&Permission::to_bits_str(perms)).unwrap(), perms);
+/// ```
 #[derive(EnumSetType, Ord, PartialOrd, Debug, Hash)]
 #[enumset(serialize_repr = "u64")]
 #[non_exhaustive]
@@ -35,6 +50,7 @@ pub enum Permission {
     ReadMessageHistory = 16,
     MentionEveryone = 17,
     UseExternalEmojis = 18,
+    ViewGuildInsights = 19,
     Connect = 20,
     Speak = 21,
     MuteMembers = 22,
@@ -48,6 +64,37 @@ pub enum Permission {
     ManageRoles = 28,
     ManageWebhooks = 29,
     ManageEmojis = 30,
+    UseApplicationCommands = 31,
+    RequestToSpeak = 32,
+    ManageEvents = 33,
+    ManageThreads = 34,
+    CreatePublicThreads = 35,
+    CreatePrivateThreads = 36,
+    UseExternalStickers = 37,
+    SendMessagesInThreads = 38,
+    UseEmbeddedActivities = 39,
+    ModerateMembers = 40,
+}
+impl Permission {
+    /// Parses a permission bitfield encoded as a decimal string, as used by the Discord API.
+    ///
+    /// Permissions not known to this library are ignored.
+    ///
+    /// ```rust
+    /// # use minnie_model::types::Permission;
+    /// let perms = Permission::from_bits_str("2048").unwrap();
+    /// assert_eq!(perms, Permission::SendMessages);
+    /// assert_eq!(Permission::to_bits_str(perms), "2048");
+    /// ```
+    pub fn from_bits_str(bits: &str) -> Result<EnumSet<Permission>> {
+        let bits: u64 = bits.parse().invalid_input("Could not parse permission bitfield.")?;
+        Ok(EnumSet::from_u64_truncated(bits))
+    }
+
+    /// Encodes a permission bitfield as a decimal string, as used by the Discord API.
+    pub fn to_bits_str(perms: EnumSet<Permission>) -> String {
+        perms.as_u64().to_string()
+    }
 }
 
 /// A type containing the bot application's client secret. Used for OAuth operations.
@@ -114,7 +161,7 @@ macro_rules! token_type {
         }
         impl fmt::Debug for $name {
             fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-                f.write_str("<discord token omitted>")
+                f.write_str(concat!(stringify!($name), "(***)"))
             }
         }
         impl From<$name> for Arc<str> {
@@ -126,6 +173,24 @@ macro_rules! token_type {
 }
 
 /// A type containing a bot token.
+///
+/// The token is never included in the `Debug` output of this type, or of any type containing it.
+///
+/// # Example
+///
+/// ```rust
+/// # use minnie_model::types::DiscordToken;
+/// let raw = "MTIzNDU2Nzg5MDEyMzQ1Njc4.X0aBcD.abcdefghijklmnopqrstuvwxyz0";
+/// let token = DiscordToken::new(raw).unwrap();
+/// let debug = format!("{:?}", token);
+/// assert_eq!(debug, "DiscordToken(***)");
+/// for segment in raw.split('.') {
+///     assert!(!debug.contains(segment));
+/// }
+/// assert_eq!(token.user_id().unwrap(), 123456789012345678u64);
+///
+/// assert!(DiscordToken::new("not.a.token").is_err());
+/// ```
 #[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
 #[repr(transparent)]
 pub struct DiscordToken(Arc<str>);
@@ -136,7 +201,7 @@ impl DiscordToken {
         let tok_data = if has_bot { &tok[4..] } else { &tok };
         let split: Vec<_> = tok_data.split('.').collect();
         ensure!(split.len() == 3, InvalidInput, "Tokens consist of 3 sections separated by '.'");
-        for section in split {
+        for section in &split {
             ensure!(section.len() >= 1, InvalidInput, "Segments cannot be empty.");
             for char in section.chars() {
                 match char {
@@ -145,9 +210,27 @@ impl DiscordToken {
                 }
             }
         }
+        ensure!(
+            Self::decode_user_id(split[0]).is_some(),
+            InvalidInput, "The first segment of a token must encode the bot's user ID.",
+        );
 
         Ok(DiscordToken(if has_bot { tok.into() } else { format!("Bot {}", tok).into() }))
     }
+
+    fn decode_user_id(segment: &str) -> Option<UserId> {
+        let decoded = base64::decode_config(segment, base64::URL_SAFE_NO_PAD).ok()?;
+        let id = std::str::from_utf8(&decoded).ok()?.parse::<u64>().ok()?;
+        Some(UserId::from(id))
+    }
+
+    /// Returns the user ID of the bot this token belongs to.
+    ///
+    /// This is decoded from the token itself, and does not check that the token is valid.
+    pub fn user_id(&self) -> Option<UserId> {
+        let tok_data = self.0.strip_prefix("Bot ").unwrap_or(&*self.0);
+        Self::decode_user_id(tok_data.split('.').next()?)
+    }
 }
 token_type!(DiscordToken);
 
@@ -164,6 +247,30 @@ impl DiscordBearerToken {
 }
 token_type!(DiscordBearerToken);
 
+/// A type containing the token of an interaction, used to respond to it.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct InteractionToken(Arc<str>);
+impl InteractionToken {
+    fn new_0(tok: String) -> Result<InteractionToken> {
+        ensure!(!tok.is_empty(), InvalidInput, "Interaction tokens cannot be empty.");
+        Ok(InteractionToken(tok.into()))
+    }
+}
+token_type!(InteractionToken);
+
+/// A type containing the token of a webhook, used to execute it.
+#[derive(Serialize, Deserialize, Clone, Ord, PartialOrd, Eq, PartialEq, Hash)]
+#[repr(transparent)]
+pub struct WebhookToken(Arc<str>);
+impl WebhookToken {
+    fn new_0(tok: String) -> Result<WebhookToken> {
+        ensure!(!tok.is_empty(), InvalidInput, "Webhook tokens cannot be empty.");
+        Ok(WebhookToken(tok.into()))
+    }
+}
+token_type!(WebhookToken);
+
 /// A color used in Discord messages/etc.
 ///
 /// This is a sRGB color with no alpha channel. It is encoded as `0xrrggbb`.
@@ -216,14 +323,78 @@ impl EmojiRef {
         EmojiRef::Builtin(emoji.into())
     }
 
+    /// Creates a reference to a built-in emoji from its unicode representation.
+    pub fn from_unicode(emoji: &str) -> EmojiRef {
+        EmojiRef::Builtin(emoji.to_string().into())
+    }
+
     /// Creates a reference to a custom emoji.
-    pub fn custom(id: impl Into<EmojiId>) -> EmojiRef {
-        EmojiRef::Custom(None, id.into())
+    pub fn custom(name: &str, id: impl Into<EmojiId>) -> EmojiRef {
+        EmojiRef::Custom(Some(name.to_string().into()), id.into())
+    }
+
+    /// Returns this emoji in the form used in the path of API calls, percent-encoding any
+    /// characters that are not allowed in an URL.
+    pub fn url_encoded(&self) -> String {
+        let mut buf = String::new();
+        for byte in self.to_string().bytes() {
+            match byte {
+                b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b':' =>
+                    buf.push(byte as char),
+                _ => buf.push_str(&format!("%{:02X}", byte)),
+            }
+        }
+        buf
     }
 }
 impl From<EmojiId> for EmojiRef {
     fn from(id: EmojiId) -> Self {
-        EmojiRef::custom(id)
+        EmojiRef::Custom(None, id)
+    }
+}
+impl <'a> From<&'a EmojiRef> for EmojiRef {
+    fn from(emoji: &'a EmojiRef) -> Self {
+        emoji.clone()
+    }
+}
+impl FromStr for EmojiRef {
+    type Err = Error;
+
+    /// Parses an emoji from either its unicode representation, the `name:id` form, or the
+    /// `<:name:id>` form used in message content.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// # use minnie_model::types::*;
+    /// let flag: EmojiRef = "🇯🇵".parse().unwrap();
+    /// assert_eq!(flag, EmojiRef::from_unicode("🇯🇵"));
+    /// assert_eq!(flag.url_encoded(), "%F0%9F%87%AF%F0%9F%87%B5");
+    ///
+    /// let custom: EmojiRef = "<:minnie:123456789>".parse().unwrap();
+    /// assert_eq!(custom, EmojiRef::custom("minnie", EmojiId(Snowflake(123456789))));
+    /// assert_eq!(custom.url_encoded(), "minnie:123456789");
+    /// ```
+    fn from_str(s: &str) -> Result<Self> {
+        ensure!(!s.is_empty(), InvalidInput, "Emoji cannot be empty.");
+        let inner = if s.starts_with('<') && s.ends_with('>') && s.len() >= 2 {
+            let inner = &s[1..s.len() - 1];
+            if inner.starts_with("a:") { &inner[2..] } else { inner.trim_start_matches(':') }
+        } else {
+            s
+        };
+        if let Some(pos) = inner.rfind(':') {
+            let (name, id) = (&inner[..pos], &inner[pos + 1..]);
+            let is_name = !name.is_empty() &&
+                name.bytes().all(|x| x.is_ascii_alphanumeric() || x == b'_');
+            if is_name {
+                if let Ok(id) = id.parse::<u64>() {
+                    return Ok(EmojiRef::custom(name, EmojiId(Snowflake(id))));
+                }
+            }
+        }
+        ensure!(inner.len() == s.len(), InvalidInput, "Could not parse custom emoji.");
+        Ok(EmojiRef::from_unicode(s))
     }
 }
 impl fmt::Display for EmojiRef {
@@ -370,6 +541,11 @@ impl Snowflake {
         UNIX_EPOCH + Duration::from_millis(self.timestamp_raw() + 1420070400000)
     }
 
+    /// Retrieves the time the object this snowflake identifies was created.
+    pub fn created_at(self) -> DateTime<Utc> {
+        self.timestamp().into()
+    }
+
     /// Receives the worker thread ID of this snowflake.
     pub fn worker(self) -> u8 {
         (self.0 >> 17) as u8 & 0x1F
@@ -426,6 +602,11 @@ pub struct ApplicationId(pub Snowflake);
 #[serde(transparent)]
 pub struct AttachmentId(pub Snowflake);
 
+/// An audit log entry ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct AuditLogEntryId(pub Snowflake);
+
 /// A category ID.
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[serde(transparent)]
@@ -441,6 +622,11 @@ pub struct ChannelId(pub Snowflake);
 #[serde(transparent)]
 pub struct EmojiId(pub Snowflake);
 
+/// A forum tag ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct ForumTagId(pub Snowflake);
+
 /// A guild ID.
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[serde(transparent)]
@@ -452,6 +638,16 @@ impl GuildId {
     }
 }
 
+/// An integration ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct IntegrationId(pub Snowflake);
+
+/// An interaction ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct InteractionId(pub Snowflake);
+
 /// A message ID.
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[serde(transparent)]
@@ -462,6 +658,21 @@ pub struct MessageId(pub Snowflake);
 #[serde(transparent)]
 pub struct RoleId(pub Snowflake);
 
+/// A scheduled event ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct ScheduledEventId(pub Snowflake);
+
+/// A stage instance ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct StageInstanceId(pub Snowflake);
+
+/// A sticker ID.
+#[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+#[serde(transparent)]
+pub struct StickerId(pub Snowflake);
+
 /// An user ID.
 #[derive(Serialize, Deserialize, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
 #[serde(transparent)]
@@ -474,6 +685,12 @@ pub struct WebhookId(pub Snowflake);
 
 macro_rules! id_structs {
     ($($name:ident)*) => {$(
+        impl $name {
+            /// Retrieves the time the object this ID identifies was created.
+            pub fn created_at(self) -> DateTime<Utc> {
+                self.0.created_at()
+            }
+        }
         impl From<Snowflake> for $name {
             fn from(s: Snowflake) -> $name {
                 $name(s)
@@ -523,8 +740,47 @@ macro_rules! id_structs {
 }
 
 id_structs! {
-    ApplicationId AttachmentId CategoryId ChannelId EmojiId GuildId MessageId RoleId
-    UserId WebhookId
+    ApplicationId AttachmentId AuditLogEntryId CategoryId ChannelId EmojiId ForumTagId GuildId
+    IntegrationId InteractionId MessageId RoleId ScheduledEventId StageInstanceId StickerId UserId
+    WebhookId
+}
+
+/// A mention of a user, channel, or role.
+///
+/// This formats using Discord's mention syntax, e.g. `<@user>`, `<#channel>` or `<@&role>`.
+#[derive(Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
+pub enum Mention {
+    User(UserId),
+    Channel(ChannelId),
+    Role(RoleId),
+}
+impl fmt::Display for Mention {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Mention::User(id) => write!(fmt, "<@{}>", id.0),
+            Mention::Channel(id) => write!(fmt, "<#{}>", id.0),
+            Mention::Role(id) => write!(fmt, "<@&{}>", id.0),
+        }
+    }
+}
+
+impl ChannelId {
+    /// Returns a value that formats as a mention of this channel.
+    pub fn mention(self) -> Mention {
+        Mention::Channel(self)
+    }
+}
+impl RoleId {
+    /// Returns a value that formats as a mention of this role.
+    pub fn mention(self) -> Mention {
+        Mention::Role(self)
+    }
+}
+impl UserId {
+    /// Returns a value that formats as a mention of this user.
+    pub fn mention(self) -> Mention {
+        Mention::User(self)
+    }
 }
 
 impl GuildId {
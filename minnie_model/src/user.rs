@@ -1,6 +1,7 @@
 //! Types relating to Discord users.
 
 use crate::guild::*;
+use crate::http::{ImageFormat, cdn_url, default_avatar_url};
 use crate::serde::*;
 use crate::types::*;
 use std::borrow::Cow;
@@ -65,6 +66,20 @@ pub struct User {
     pub bot: bool,
     pub system: Option<bool>,
 }
+impl User {
+    /// Returns the URL of this user's avatar.
+    ///
+    /// If the user has no custom avatar, this returns the URL of the default avatar Discord
+    /// shows for them, which is always a PNG. Animated avatars may be retrieved as a GIF.
+    ///
+    /// If `size` is given, it is rounded to a power of two between 16 and 4096.
+    pub fn avatar_url(&self, format: ImageFormat, size: Option<u32>) -> String {
+        match &self.avatar {
+            Some(hash) => cdn_url("avatars", (self.id.0).0, hash, format, size),
+            None => default_avatar_url(self.discriminator.0, size),
+        }
+    }
+}
 into_id!(User, UserId, id);
 
 /// A struct representing a user with additional member information. Used as part of
@@ -141,7 +156,7 @@ pub enum UserPremiumType {
 
 /// The connection status of an user.
 #[derive(Serialize, Deserialize, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Debug, Hash)]
-#[serde(rename = "lowercase")]
+#[serde(rename_all = "lowercase")]
 #[non_exhaustive]
 pub enum UserStatus {
     Online,
@@ -177,14 +192,15 @@ pub struct Presence {
     pub game: Option<Activity>,
     pub guild_id: Option<GuildId>,
     pub status: Option<UserStatus>,
-    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    #[serde(default, skip_serializing_if = "Vec::is_empty", rename = "activities")]
     pub activites: Vec<Activity>,
     pub client_status: Option<ClientStatus>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub premium_since: Option<DateTime<Utc>>,
 
     #[serde(default, skip_serializing_if = "utils::if_false", rename = "$malformed")]
-    /// This field is set to true if this `Presence Update` packet could not be parsed.
+    /// This field is set to true if some fields of this `Presence Update` packet could not be
+    /// parsed, and were left empty.
     pub malformed: bool,
 }
 
@@ -197,7 +213,9 @@ pub enum ActivityType {
     Game = 0,
     Streaming = 1,
     Listening = 2,
+    Watching = 3,
     CustomStatus = 4,
+    Competing = 5,
     #[serde(other)]
     Unknown = i32::max_value(),
 }
@@ -294,7 +312,43 @@ impl Activity {
         }
     }
 
-    /// Creates a new custom status.
+    /// Creates a new activity shown as "Playing {name}".
+    pub fn playing(name: impl Into<Cow<'static, str>>) -> Self {
+        Activity::new(ActivityType::Game, name)
+    }
+
+    /// Creates a new activity shown as "Streaming {name}".
+    ///
+    /// Discord currently only accepts Twitch and YouTube URLs for streams.
+    pub fn streaming(
+        name: impl Into<Cow<'static, str>>, url: impl Into<Cow<'static, str>>,
+    ) -> Self {
+        Activity::new(ActivityType::Streaming, name).with_url(url)
+    }
+
+    /// Creates a new activity shown as "Listening to {name}".
+    pub fn listening(name: impl Into<Cow<'static, str>>) -> Self {
+        Activity::new(ActivityType::Listening, name)
+    }
+
+    /// Creates a new activity shown as "Watching {name}".
+    pub fn watching(name: impl Into<Cow<'static, str>>) -> Self {
+        Activity::new(ActivityType::Watching, name)
+    }
+
+    /// Creates a new activity shown as "Competing in {name}".
+    pub fn competing(name: impl Into<Cow<'static, str>>) -> Self {
+        Activity::new(ActivityType::Competing, name)
+    }
+
+    /// Creates a new custom status with the given text and no emoji.
+    ///
+    /// This is shown as the status text itself, without any prefix such as "Playing".
+    pub fn custom(text: impl Into<Cow<'static, str>>) -> Self {
+        Activity::custom_status(None, text)
+    }
+
+    /// Creates a new custom status with an optional emoji.
     pub fn custom_status(emoji: Option<EmojiRef>, status: impl Into<Cow<'static, str>>) -> Self {
         let mut activity = Activity::new(ActivityType::CustomStatus, "Custom Status");
         activity.emoji = emoji;